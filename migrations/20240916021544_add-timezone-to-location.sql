@@ -0,0 +1,3 @@
+-- null until the first forecast for the location tells us its timezone
+alter table Location
+add column timezone text;
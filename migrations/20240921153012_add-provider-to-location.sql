@@ -0,0 +1,2 @@
+alter table Location
+add column provider text not null default 'open_meteo';
@@ -0,0 +1,6 @@
+alter table Location
+add column position integer not null default 0;
+
+update Location
+set
+  position = id;
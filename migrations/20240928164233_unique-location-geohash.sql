@@ -0,0 +1,57 @@
+-- history whose location is already gone has nobody to go to, and would trip the foreign key
+delete from HistoricalForecast
+where
+  location_id not in (
+    select
+      id
+    from
+      Location
+  );
+
+-- keep the oldest row for each geohash, and hand it the history of its duplicates
+update HistoricalForecast
+set
+  location_id = (
+    select
+      min(keep.id)
+    from
+      Location keep
+      join Location dup on dup.geohash = keep.geohash
+    where
+      dup.id = HistoricalForecast.location_id
+  );
+
+update Setting
+set
+  value = (
+    select
+      min(keep.id)
+    from
+      Location keep
+      join Location dup on dup.geohash = keep.geohash
+    where
+      dup.id = cast(Setting.value as integer)
+  )
+where
+  key = 'current_location'
+  and exists (
+    select
+      1
+    from
+      Location
+    where
+      id = cast(Setting.value as integer)
+  );
+
+delete from Location
+where
+  id not in (
+    select
+      min(id)
+    from
+      Location
+    group by
+      geohash
+  );
+
+create unique index Location_geohash on Location (geohash);
@@ -0,0 +1,10 @@
+-- timestamps used to be `Utc::now().to_string()`, e.g. `2024-09-21 15:30:12.123456789 UTC`,
+-- which doesn't sort. Rewrite them as RFC 3339 in UTC so text order is time order.
+update HistoricalForecast
+set
+  timestamp = coalesce(
+    strftime('%Y-%m-%dT%H:%M:%fZ', replace(timestamp, ' UTC', '')),
+    '1970-01-01T00:00:00.000Z'
+  );
+
+create index HistoricalForecast_location_timestamp on HistoricalForecast (location_id, timestamp);
@@ -0,0 +1,104 @@
+-- One row per variable per hour (or day) per fetch, so history can be queried without parsing
+-- `HistoricalForecast.response`. Values keep the unit they were fetched in.
+create table HourlyValue (
+  forecast_id integer not null,
+  location_id integer not null,
+  fetched_at text not null,
+  -- RFC 3339, UTC
+  valid_time text not null,
+  variable text not null,
+  value real,
+  unit text not null,
+  primary key (forecast_id, valid_time, variable),
+  foreign key (forecast_id) --
+  references HistoricalForecast (id) --
+  on delete cascade --
+  on update no action,
+  foreign key (location_id) --
+  references Location (id) --
+  on delete cascade --
+  on update no action
+);
+
+create index HourlyValue_location_variable_time on HourlyValue (location_id, variable, valid_time);
+
+create table DailyValue (
+  forecast_id integer not null,
+  location_id integer not null,
+  fetched_at text not null,
+  -- the calendar date where the location is
+  valid_date text not null,
+  variable text not null,
+  value real,
+  unit text not null,
+  primary key (forecast_id, valid_date, variable),
+  foreign key (forecast_id) --
+  references HistoricalForecast (id) --
+  on delete cascade --
+  on update no action,
+  foreign key (location_id) --
+  references Location (id) --
+  on delete cascade --
+  on update no action
+);
+
+create index DailyValue_location_variable_date on DailyValue (location_id, variable, valid_date);
+
+-- backfill from the responses already stored
+insert
+or ignore into HourlyValue (
+  forecast_id,
+  location_id,
+  fetched_at,
+  valid_time,
+  variable,
+  value,
+  unit
+)
+select
+  forecast.id,
+  forecast.location_id,
+  forecast.timestamp,
+  strftime(
+    '%Y-%m-%dT%H:%M:%fZ',
+    step.value,
+    (-json_extract(forecast.response, '$.utc_offset_seconds')) || ' seconds'
+  ),
+  series.key,
+  json_extract(series.value, '$[' || step.key || ']'),
+  coalesce(json_extract(forecast.response, '$.hourly_units.' || series.key), '')
+from
+  HistoricalForecast forecast,
+  json_each(forecast.response, '$.hourly.time') step,
+  json_each(forecast.response, '$.hourly') series
+where
+  series.key != 'time'
+  and series.type = 'array'
+  and json_type(series.value, '$[' || step.key || ']') in ('integer', 'real', 'null');
+
+insert
+or ignore into DailyValue (
+  forecast_id,
+  location_id,
+  fetched_at,
+  valid_date,
+  variable,
+  value,
+  unit
+)
+select
+  forecast.id,
+  forecast.location_id,
+  forecast.timestamp,
+  step.value,
+  series.key,
+  json_extract(series.value, '$[' || step.key || ']'),
+  coalesce(json_extract(forecast.response, '$.daily_units.' || series.key), '')
+from
+  HistoricalForecast forecast,
+  json_each(forecast.response, '$.daily.time') step,
+  json_each(forecast.response, '$.daily') series
+where
+  series.key != 'time'
+  and series.type = 'array'
+  and json_type(series.value, '$[' || step.key || ']') in ('integer', 'real', 'null');
@@ -1,6 +1,10 @@
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Offset, Utc};
 use serde::{Deserialize, Serialize};
 use vizia::prelude::*;
 
+use crate::timezone::LocalZone;
+use crate::units::{convert, Quantity, UnitPreferences};
+
 #[derive(Copy, Clone, Data, Serialize, Default, Deserialize, Debug)]
 pub struct LatLng {
   pub lat: f64,
@@ -24,116 +28,323 @@ pub struct Meteo {
   pub daily: Daily,
 }
 
-#[derive(Data, Lens, Debug, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
-pub struct Current {
-  pub time: String,
-  pub interval: f64,
-  #[serde(rename = "temperature_2m")]
-  pub temperature_2_m: f64,
-  #[serde(rename = "relative_humidity_2m")]
-  pub relative_humidity_2_m: f64,
-  pub apparent_temperature: f64,
-  pub is_day: f64,
-  pub precipitation: f64,
-  pub rain: f64,
-  pub showers: f64,
-  pub snowfall: f64,
-  pub weather_code: i64,
-  pub cloud_cover: f64,
-  pub pressure_msl: f64,
-  pub surface_pressure: f64,
-  #[serde(rename = "wind_speed_10m")]
-  pub wind_speed_10_m: f64,
-  #[serde(rename = "wind_direction_10m")]
-  pub wind_direction_10_m: f64,
-  #[serde(rename = "wind_gusts_10m")]
-  pub wind_gusts_10_m: f64,
+// INFO: this is the single source of truth for a variable group. Each entry becomes a field on the
+// values struct and the units struct, lands in the list of names that builds the request, and
+// gets converted between unit systems if it has a quantity. Adding a variable is one line below.
+macro_rules! open_meteo_variables {
+  (
+    $values:ident, $units:ident, $variables:ident,
+    { $($extra:ident: $extra_ty:ty),* $(,)? }
+    { $($field:ident: $ty:ty => $name:literal $(as $quantity:ident)?),* $(,)? }
+  ) => {
+    #[derive(Data, Lens, Debug, Default, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
+    pub struct $values {
+      $(pub $extra: $extra_ty,)*
+      $(
+        // older cached responses may predate a variable
+        #[serde(rename = $name, default)]
+        pub $field: $ty,
+      )*
+    }
+
+    #[derive(Data, Lens, Debug, Default, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
+    pub struct $units {
+      $(pub $extra: String,)*
+      $(
+        #[serde(rename = $name, default)]
+        pub $field: String,
+      )*
+    }
+
+    pub const $variables: &[&str] = &[$($name,)*];
+
+    impl $values {
+      /// Every numeric variable alongside its values, in request order.
+      pub fn variables(&self) -> Vec<(&'static str, Vec<Option<f64>>)> {
+        let mut variables = vec![];
+        $(
+          if let Some(values) = VariableValues::numbers(&self.$field) {
+            variables.push(($name, values));
+          }
+        )*
+        variables
+      }
+
+      /// Cuts every series down to its first `len` entries.
+      pub fn truncate(&mut self, len: usize) {
+        $(Series::truncate_series(&mut self.$extra, len);)*
+        $(Series::truncate_series(&mut self.$field, len);)*
+      }
+
+      fn convert_units(&mut self, units: &mut $units, prefs: &UnitPreferences) {
+        $($(
+          VariableValues::convert(
+            &mut self.$field,
+            &mut units.$field,
+            Quantity::$quantity.label(prefs),
+          );
+        )?)*
+      }
+    }
+
+    impl $units {
+      pub fn unit(&self, name: &str) -> Option<&str> {
+        match name {
+          $($name => Some(self.$field.as_str()),)*
+          _ => None,
+        }
+      }
+    }
+  };
 }
 
-#[derive(Data, Lens, Debug, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
-pub struct CurrentUnits {
-  pub time: String,
-  pub interval: String,
-  #[serde(rename = "temperature_2m")]
-  pub temperature_2_m: String,
-  #[serde(rename = "relative_humidity_2m")]
-  pub relative_humidity_2_m: String,
-  pub apparent_temperature: String,
-  pub is_day: String,
-  pub precipitation: String,
-  pub rain: String,
-  pub showers: String,
-  pub snowfall: String,
-  pub weather_code: String,
-  pub cloud_cover: String,
-  pub pressure_msl: String,
-  pub surface_pressure: String,
-  #[serde(rename = "wind_speed_10m")]
-  pub wind_speed_10_m: String,
-  #[serde(rename = "wind_direction_10m")]
-  pub wind_direction_10_m: String,
-  #[serde(rename = "wind_gusts_10m")]
-  pub wind_gusts_10_m: String,
+open_meteo_variables!(Current, CurrentUnits, CURRENT_VARIABLES, {
+  time: String,
+  interval: f64,
+} {
+  temperature_2_m: Option<f64> => "temperature_2m" as Temperature,
+  relative_humidity_2_m: Option<f64> => "relative_humidity_2m",
+  apparent_temperature: Option<f64> => "apparent_temperature" as Temperature,
+  is_day: Option<f64> => "is_day",
+  precipitation: Option<f64> => "precipitation" as Precipitation,
+  rain: Option<f64> => "rain" as Precipitation,
+  showers: Option<f64> => "showers" as Precipitation,
+  snowfall: Option<f64> => "snowfall" as Snowfall,
+  weather_code: Option<i64> => "weather_code",
+  cloud_cover: Option<f64> => "cloud_cover",
+  pressure_msl: Option<f64> => "pressure_msl",
+  surface_pressure: Option<f64> => "surface_pressure",
+  wind_speed_10_m: Option<f64> => "wind_speed_10m" as WindSpeed,
+  wind_direction_10_m: Option<f64> => "wind_direction_10m",
+  wind_gusts_10_m: Option<f64> => "wind_gusts_10m" as WindSpeed,
+});
+
+open_meteo_variables!(Hourly, HourlyUnits, HOURLY_VARIABLES, {
+  time: Vec<String>,
+} {
+  temperature_2_m: Vec<Option<f64>> => "temperature_2m" as Temperature,
+  relative_humidity_2_m: Vec<Option<f64>> => "relative_humidity_2m",
+  dew_point_2_m: Vec<Option<f64>> => "dew_point_2m" as Temperature,
+  precipitation_probability: Vec<Option<f64>> => "precipitation_probability",
+  precipitation: Vec<Option<f64>> => "precipitation" as Precipitation,
+  rain: Vec<Option<f64>> => "rain" as Precipitation,
+  showers: Vec<Option<f64>> => "showers" as Precipitation,
+  snowfall: Vec<Option<f64>> => "snowfall" as Snowfall,
+  weather_code: Vec<Option<f64>> => "weather_code",
+  visibility: Vec<Option<f64>> => "visibility",
+  wind_speed_10_m: Vec<Option<f64>> => "wind_speed_10m" as WindSpeed,
+  wind_direction_10_m: Vec<Option<f64>> => "wind_direction_10m",
+  wind_gusts_10_m: Vec<Option<f64>> => "wind_gusts_10m" as WindSpeed,
+});
+
+open_meteo_variables!(Daily, DailyUnits, DAILY_VARIABLES, {
+  time: Vec<String>,
+} {
+  weather_code: Vec<Option<f64>> => "weather_code",
+  temperature_2_m_max: Vec<Option<f64>> => "temperature_2m_max" as Temperature,
+  temperature_2_m_min: Vec<Option<f64>> => "temperature_2m_min" as Temperature,
+  sunrise: Vec<String> => "sunrise",
+  sunset: Vec<String> => "sunset",
+  daylight_duration: Vec<Option<f64>> => "daylight_duration",
+  uv_index_max: Vec<Option<f64>> => "uv_index_max",
+  precipitation_sum: Vec<Option<f64>> => "precipitation_sum" as Precipitation,
+  rain_sum: Vec<Option<f64>> => "rain_sum" as Precipitation,
+  snowfall_sum: Vec<Option<f64>> => "snowfall_sum" as Snowfall,
+  precipitation_probability_max: Vec<Option<f64>> => "precipitation_probability_max",
+});
+
+/// The shapes a variable's values can take in a response. Open-Meteo sends `null` wherever a
+/// model has no value, so every numeric variable is optional.
+trait VariableValues {
+  /// The values as numbers, or `None` for text variables like `sunrise`.
+  fn numbers(&self) -> Option<Vec<Option<f64>>>;
+
+  /// Converts the values from `unit` into `target` and relabels `unit` if the two are compatible.
+  fn convert(&mut self, unit: &mut String, target: &str);
 }
 
-#[derive(Data, Lens, Debug, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
-pub struct Daily {
-  pub time: Vec<String>,
-  pub weather_code: Vec<f64>,
-  #[serde(rename = "temperature_2m_max")]
-  pub temperature_2_m_max: Vec<f64>,
-  #[serde(rename = "temperature_2m_min")]
-  pub temperature_2_m_min: Vec<f64>,
-  pub sunrise: Vec<String>,
-  pub sunset: Vec<String>,
-  pub daylight_duration: Vec<f64>,
-  pub uv_index_max: Vec<f64>,
-  pub precipitation_sum: Vec<f64>,
-  pub precipitation_probability_max: Vec<f64>,
+impl VariableValues for Option<f64> {
+  fn numbers(&self) -> Option<Vec<Option<f64>>> {
+    Some(vec![*self])
+  }
+
+  fn convert(&mut self, unit: &mut String, target: &str) {
+    if convert(0.0, unit, target).is_none() {
+      return;
+    }
+    *self = self.and_then(|value| convert(value, unit, target));
+    *unit = String::from(target);
+  }
 }
 
-#[derive(Data, Lens, Debug, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
-pub struct DailyUnits {
-  pub time: String,
-  pub weather_code: String,
-  #[serde(rename = "temperature_2m_max")]
-  pub temperature_2_m_max: String,
-  #[serde(rename = "temperature_2m_min")]
-  pub temperature_2_m_min: String,
-  pub sunrise: String,
-  pub sunset: String,
-  pub daylight_duration: String,
-  pub uv_index_max: String,
-  pub precipitation_sum: String,
-  pub precipitation_probability_max: String,
+impl VariableValues for Option<i64> {
+  fn numbers(&self) -> Option<Vec<Option<f64>>> {
+    Some(vec![self.map(|value| value as f64)])
+  }
+
+  fn convert(&mut self, _unit: &mut String, _target: &str) {}
 }
 
-#[derive(Data, Lens, Debug, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
-pub struct Hourly {
-  pub time: Vec<String>,
-  #[serde(rename = "temperature_2m")]
-  pub temperature_2_m: Vec<f64>,
-  #[serde(rename = "relative_humidity_2m")]
-  pub relative_humidity_2_m: Vec<f64>,
-  pub precipitation_probability: Vec<f64>,
-  pub precipitation: Vec<f64>,
-  pub rain: Vec<f64>,
-  pub showers: Vec<f64>,
-  pub snowfall: Vec<f64>,
-  pub weather_code: Vec<f64>,
+impl VariableValues for Vec<Option<f64>> {
+  fn numbers(&self) -> Option<Vec<Option<f64>>> {
+    Some(self.clone())
+  }
+
+  fn convert(&mut self, unit: &mut String, target: &str) {
+    if convert(0.0, unit, target).is_none() {
+      return;
+    }
+    for value in self.iter_mut() {
+      *value = value.and_then(|value| convert(value, unit, target));
+    }
+    *unit = String::from(target);
+  }
 }
 
-#[derive(Data, Lens, Debug, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
-pub struct HourlyUnits {
-  pub time: String,
-  #[serde(rename = "temperature_2m")]
-  pub temperature_2_m: String,
-  #[serde(rename = "relative_humidity_2m")]
-  pub relative_humidity_2_m: String,
-  pub precipitation_probability: String,
-  pub precipitation: String,
-  pub rain: String,
-  pub showers: String,
-  pub snowfall: String,
-  pub weather_code: String,
+impl VariableValues for Vec<String> {
+  fn numbers(&self) -> Option<Vec<Option<f64>>> {
+    None
+  }
+
+  fn convert(&mut self, _unit: &mut String, _target: &str) {}
+}
+
+trait Series {
+  fn truncate_series(&mut self, len: usize);
+}
+
+impl<T> Series for Vec<T> {
+  fn truncate_series(&mut self, len: usize) {
+    self.truncate(len);
+  }
+}
+
+// single readings aren't series, so there's nothing to cut
+impl Series for String {
+  fn truncate_series(&mut self, _len: usize) {}
+}
+
+impl Series for f64 {
+  fn truncate_series(&mut self, _len: usize) {}
+}
+
+impl<T> Series for Option<T> {
+  fn truncate_series(&mut self, _len: usize) {}
+}
+
+impl Meteo {
+  /// The timezone the forecast was requested in. Every `time`, `sunrise` and `sunset` string in
+  /// the response is local to it.
+  pub fn zone(&self) -> LocalZone {
+    LocalZone::parse(
+      &self.timezone,
+      FixedOffset::east_opt(self.utc_offset_seconds as i32).unwrap_or(Utc.fix()),
+    )
+  }
+
+  pub fn parse_time(&self, time: &str) -> Option<DateTime<FixedOffset>> {
+    let time = NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M").ok()?;
+    self
+      .zone()
+      .from_local(&time)
+  }
+
+  /// `now` on the forecast's local clock.
+  pub fn local_time(&self, now: DateTime<Utc>) -> NaiveDateTime {
+    self
+      .zone()
+      .to_local(&now)
+  }
+
+  /// The first entry of `times`, an hourly or daily series, that isn't over yet at `now`. The
+  /// series start a day back, and a stale forecast may be all past, in which case it's the
+  /// length of the series.
+  pub fn index_of_now(&self, times: &[String], now: DateTime<Utc>) -> usize {
+    let local = self
+      .local_time(now)
+      .format("%Y-%m-%dT%H:%M")
+      .to_string();
+    times
+      .iter()
+      .position(|time| {
+        // dates compare by the day, timestamps by the hour
+        let precision = time
+          .len()
+          .min(13);
+        time.as_str() >= &local[..precision]
+      })
+      .unwrap_or(times.len())
+  }
+
+  /// Whether the sun is up at `time`, a local timestamp in the same format as `hourly.time`,
+  /// going by that day's sunrise and sunset. With nothing to go on it assumes daytime.
+  pub fn is_day_at(&self, time: &str) -> bool {
+    let Some(day) = time
+      .get(..10)
+      .and_then(|date| {
+        self
+          .daily
+          .time
+          .iter()
+          .position(|day| day == date)
+      })
+    else {
+      return true;
+    };
+    // the sun never sets in polar summer, or never rises in polar winter
+    match self
+      .daily
+      .daylight_duration
+      .get(day)
+      .copied()
+      .flatten()
+    {
+      Some(seconds) if seconds <= 0.0 => return false,
+      Some(seconds) if seconds >= 86_400.0 => return true,
+      _ => {}
+    }
+    match (
+      self
+        .daily
+        .sunrise
+        .get(day),
+      self
+        .daily
+        .sunset
+        .get(day),
+    ) {
+      // same format, so comparing the text compares the times
+      (Some(sunrise), Some(sunset)) => sunrise.as_str() <= time && time < sunset.as_str(),
+      _ => true,
+    }
+  }
+
+  /// Whether it's daytime in `current`, from the model if it says so.
+  pub fn is_day_now(&self) -> bool {
+    match self
+      .current
+      .is_day
+    {
+      Some(is_day) => is_day > 0.0,
+      None => self.is_day_at(
+        &self
+          .current
+          .time,
+      ),
+    }
+  }
+
+  /// Rewrites every unit-bearing value (and its label) into the units chosen in `prefs`.
+  /// Forecasts cached under a different unit preference go through here before display.
+  pub fn convert_units(&mut self, prefs: &UnitPreferences) {
+    self
+      .current
+      .convert_units(&mut self.current_units, prefs);
+    self
+      .hourly
+      .convert_units(&mut self.hourly_units, prefs);
+    self
+      .daily
+      .convert_units(&mut self.daily_units, prefs);
+  }
 }
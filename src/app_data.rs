@@ -1,33 +1,134 @@
-use pollster::FutureExt as _;
-use reqwest::Client;
+use std::future::Future;
+
+use chrono::{DateTime, Utc};
+use tokio::runtime::Handle;
 use vizia::prelude::*;
 
 use crate::api_models::*;
+use crate::dashboard::DashboardCell;
 use crate::db_models::*;
+use crate::http::{FetchError, HttpClient};
+use crate::providers::open_meteo::{self, OpenMeteo};
+use crate::providers::{ForecastRequest, ProviderKind};
 use crate::queries::*;
+use crate::scheduler::{RefreshSchedule, Scheduler};
+use crate::units::UnitPreferences;
 
 pub enum AppEvent {
   SetWeatherData(Option<Meteo>),
-  ConfirmLocation(String, String),
+  /// A location was saved from the form and should become the current one.
+  ConfirmLocation(Location),
   RefreshForecast,
+  /// Refetches even if the forecast on screen is still fresh.
+  ForceRefresh,
+  /// Refreshes every saved location other than the one on screen, without showing them.
+  RefreshAllLocations,
+  /// Posted by the scheduler's clock, see `scheduler::start_ticking`.
+  Tick(DateTime<Utc>),
+  SetRefreshSchedule(RefreshSchedule),
+  ChangeRefreshSchedule(RefreshSchedule),
+  SetDashboardCells(Vec<DashboardCell>),
+  ChangeDashboardCells(Vec<DashboardCell>),
+  /// A background fetch finished. Carries the refresh generation it was started under.
+  ForecastLoaded(u64, Meteo),
+  FailedToRetrieveForecast(u64, FetchError),
+  DismissError,
   Rehydrate(Location, HistoricalForecast),
-  // FailedToRetrieveForecast,
-  // UhOh,
-  // BigUhOh,
+  SetLocation(Location),
+  SetLocations(Vec<Location>),
+  SelectLocation(i64),
+  RenameLocation(i64, String),
+  DeleteLocation(i64),
+  /// Moves the location at the first index to the second.
+  MoveLocation(usize, usize),
+  SetUnits(UnitPreferences),
+  ChangeUnits(UnitPreferences),
+  SetForecastDays(u8),
+  ChangeForecastDays(u8),
+  SetRetention(RetentionPolicy),
+  ChangeRetention(RetentionPolicy),
+  CompactDatabase,
+  DatabaseCompacted(String),
+  Navigate(Screen),
 
   // might be extraneous now
   UpdateGeohash(String),
   UpdateLocationName(String),
 }
 
-#[derive(Default, Debug, Lens, Clone)]
+#[derive(Copy, Clone, Data, Debug, Default, PartialEq)]
+pub enum Screen {
+  #[default]
+  Forecast,
+  Daily,
+  Locations,
+  Settings,
+}
+
+/// Open-Meteo won't forecast further out than this.
+pub const MAX_FORECAST_DAYS: u8 = 16;
+
+#[derive(Debug, Lens, Clone)]
 pub struct AppData {
   pub weather_data: Option<Meteo>,
   pub new_geohash: String,
   pub location_confirmed: bool,
   pub saved_location: Option<Location>,
+  pub locations: Vec<Location>,
   pub forecast: Option<HistoricalForecast>,
+  /// When the forecast on screen was fetched, which is a while ago if it came from the cache.
+  pub fetched_at: Option<DateTime<Utc>>,
+  /// The wall clock as of the last scheduler tick, for anything on screen that ages with it.
+  pub now: DateTime<Utc>,
   pub new_location_name: String,
+  pub units: UnitPreferences,
+  pub screen: Screen,
+  pub forecast_days: u8,
+  pub retention: RetentionPolicy,
+  pub refresh_schedule: RefreshSchedule,
+  /// Which readings the forecast screen shows, in order.
+  pub dashboard_cells: Vec<DashboardCell>,
+  /// The outcome of the last "compact database", for the settings screen.
+  pub maintenance_status: Option<String>,
+  pub loading: bool,
+  pub fetch_error: Option<FetchError>,
+  /// Bumped by every refresh so responses that lost the race can be ignored.
+  pub refresh_generation: u64,
+  #[lens(ignore)]
+  pub scheduler: Scheduler,
+  #[lens(ignore)]
+  pub http: HttpClient,
+  #[lens(ignore)]
+  pub repo: Repository,
+}
+
+impl AppData {
+  pub fn new(repo: Repository) -> Self {
+    Self {
+      weather_data: None,
+      new_geohash: String::new(),
+      location_confirmed: false,
+      saved_location: None,
+      locations: vec![],
+      forecast: None,
+      fetched_at: None,
+      now: Utc::now(),
+      new_location_name: String::new(),
+      units: UnitPreferences::default(),
+      screen: Screen::default(),
+      forecast_days: DEFAULT_FORECAST_DAYS,
+      retention: RetentionPolicy::default(),
+      refresh_schedule: RefreshSchedule::default(),
+      dashboard_cells: DashboardCell::DEFAULT.to_vec(),
+      maintenance_status: None,
+      loading: false,
+      fetch_error: None,
+      refresh_generation: 0,
+      scheduler: Scheduler::default(),
+      http: HttpClient::default(),
+      repo,
+    }
+  }
 }
 
 impl Model for AppData {
@@ -40,29 +141,506 @@ impl Model for AppData {
       }
 
       // TODO: i still don't really love how this is being done
-      AppEvent::ConfirmLocation(new_geohash, new_name) => {
-        println!("AppEvent::ConfirmLocation");
-        self.new_geohash = new_geohash.to_string();
-        self.new_location_name = new_name.to_string();
+      AppEvent::ConfirmLocation(location) => {
+        println!("AppEvent::ConfirmLocation({:#?})", location);
+        self.new_geohash = location
+          .geohash
+          .clone();
+        self.new_location_name = location
+          .name
+          .clone();
         self.location_confirmed = true;
-        let add_result = add_location_to_db(new_name, new_geohash).block_on();
-        println!("add result: {:?}", add_result);
-        if let Ok((_, lng, lat)) = geohash::decode(new_geohash) {
-          let weather_data = get_weather_data(lat, lng);
-          let _ = ex.emit(AppEvent::SetWeatherData(weather_data));
+        self.saved_location = Some(location.clone());
+        self.clear_forecast();
+        if let Some((lat, lng)) = location.coords() {
+          let generation = self.start_refresh();
+          let request = self.forecast_request(lat, lng, location.timezone());
+          let http = self
+            .http
+            .clone();
+          let repo = self
+            .repo
+            .clone();
+          let retention = self.retention;
+          let location = location.clone();
+          run_in_background(ex, async move {
+            if let Err(e) = repo
+              .set_current_location(location.id)
+              .await
+            {
+              println!("failed to save the current location: {:?}", e);
+            }
+            let mut events = reload_locations(&repo).await;
+            events.push(forecast_result(
+              generation,
+              refresh_forecast(&repo, location, http, request, retention).await,
+            ));
+            events
+          });
         };
         println!("New State: {:#?}", self);
       }
 
       AppEvent::RefreshForecast => {
         println!("AppEvent::RefreshForecast");
-        handle_app_event_refresh_forecast(ex, self.clone());
+        if self.is_fresh(Utc::now()) {
+          println!("forecast is still fresh, not refetching");
+          return;
+        }
+        self.refresh(ex);
+        println!("New State: {:#?}", self);
+      }
+
+      AppEvent::ForceRefresh => {
+        println!("AppEvent::ForceRefresh");
+        self.refresh(ex);
+        println!("New State: {:#?}", self);
+      }
+
+      AppEvent::RefreshAllLocations => {
+        println!("AppEvent::RefreshAllLocations");
+        let current = self
+          .saved_location
+          .as_ref()
+          .map(|location| location.id);
+        let requests: Vec<_> = self
+          .locations
+          .iter()
+          .filter(|location| Some(location.id) != current)
+          .filter_map(|location| {
+            let (lat, lng) = location.coords()?;
+            let request = self.forecast_request(lat, lng, location.timezone());
+            Some((location.clone(), request))
+          })
+          .collect();
+        let http = self
+          .http
+          .clone();
+        let repo = self
+          .repo
+          .clone();
+        let retention = self.retention;
+        run_in_background(ex, async move {
+          refresh_all(&repo, &http, requests, retention).await;
+          // timezones may have been filled in
+          reload_locations(&repo).await
+        });
+      }
+
+      AppEvent::Tick(now) => {
+        self.now = *now;
+        if self.loading {
+          return;
+        }
+        if self
+          .scheduler
+          .tick(&self.refresh_schedule, *now)
+        {
+          // NOTE: the user picked the interval, so this skips the freshness check a plain
+          // `RefreshForecast` makes, even if the provider likely has nothing newer yet
+          let mut dispatched = self.refresh(ex);
+          if self
+            .refresh_schedule
+            .all_locations
+          {
+            ex.emit(AppEvent::RefreshAllLocations);
+            dispatched = true;
+          }
+          if dispatched {
+            println!("AppEvent::Tick({}): refresh due", now);
+            self
+              .scheduler
+              .attempted(*now);
+            println!("New State: {:#?}", self);
+          }
+        }
+      }
+
+      AppEvent::SetRefreshSchedule(schedule) => {
+        println!("AppEvent::SetRefreshSchedule({:?})", schedule);
+        self.refresh_schedule = *schedule;
+        println!("New State: {:#?}", self);
+      }
+
+      AppEvent::ChangeRefreshSchedule(schedule) => {
+        println!("AppEvent::ChangeRefreshSchedule({:?})", schedule);
+        let schedule = *schedule;
+        let repo = self
+          .repo
+          .clone();
+        run_in_background(ex, async move {
+          match repo
+            .save_refresh_schedule(&schedule)
+            .await
+          {
+            Ok(()) => vec![AppEvent::SetRefreshSchedule(schedule)],
+            Err(e) => {
+              println!("failed to save refresh schedule: {:?}", e);
+              vec![]
+            }
+          }
+        });
+      }
+
+      AppEvent::SetDashboardCells(cells) => {
+        println!("AppEvent::SetDashboardCells({:?})", cells);
+        self.dashboard_cells = cells.clone();
+        println!("New State: {:#?}", self);
+      }
+
+      AppEvent::ChangeDashboardCells(cells) => {
+        println!("AppEvent::ChangeDashboardCells({:?})", cells);
+        let cells = cells.clone();
+        let repo = self
+          .repo
+          .clone();
+        run_in_background(ex, async move {
+          match repo
+            .save_dashboard_cells(&cells)
+            .await
+          {
+            Ok(()) => vec![AppEvent::SetDashboardCells(cells)],
+            Err(e) => {
+              println!("failed to save dashboard cells: {:?}", e);
+              vec![]
+            }
+          }
+        });
+      }
+
+      AppEvent::ForecastLoaded(generation, meteo) => {
+        println!("AppEvent::ForecastLoaded({})", generation);
+        if *generation != self.refresh_generation {
+          println!(
+            "dropping stale forecast from refresh {} (latest is {})",
+            generation, self.refresh_generation
+          );
+          return;
+        }
+        self.loading = false;
+        self.fetch_error = None;
+        self
+          .scheduler
+          .succeeded();
+        // the units may have changed while the request was in flight
+        let mut meteo = meteo.clone();
+        meteo.convert_units(&self.units);
+        self.weather_data = Some(meteo);
+        self.now = Utc::now();
+        self.fetched_at = Some(self.now);
+        println!("New State: {:#?}", self);
+      }
+
+      AppEvent::FailedToRetrieveForecast(generation, error) => {
+        println!(
+          "AppEvent::FailedToRetrieveForecast({}, {:?})",
+          generation, error
+        );
+        if *generation != self.refresh_generation {
+          println!(
+            "dropping stale error from refresh {} (latest is {})",
+            generation, self.refresh_generation
+          );
+          return;
+        }
+        // whatever is on screen stays there, it's just getting old
+        self.loading = false;
+        self
+          .scheduler
+          .failed();
+        self.fetch_error = Some(error.clone());
+        println!("New State: {:#?}", self);
+      }
+
+      AppEvent::DismissError => {
+        println!("AppEvent::DismissError");
+        self.fetch_error = None;
         println!("New State: {:#?}", self);
       }
 
       AppEvent::Rehydrate(loc, hf) => {
         println!("AppEvent::Rehydrate({:#?})", loc);
+        // looked up for a location the user has since moved off
+        if self
+          .saved_location
+          .as_ref()
+          .is_some_and(|current| current.id != loc.id)
+        {
+          println!("dropping stale cached forecast for {}", loc.name);
+          return;
+        }
         self.saved_location = Some(loc.clone());
+        self.forecast = Some(hf.clone());
+        // show the cached forecast until a fresh one comes in
+        match hf.meteo() {
+          Ok(mut meteo) => {
+            meteo.convert_units(&self.units);
+            self.weather_data = Some(meteo);
+            self.fetched_at = Some(hf.timestamp);
+          }
+          Err(e) => println!("couldn't read the cached forecast: {:?}", e),
+        }
+        println!("New State: {:#?}", self);
+      }
+
+      AppEvent::SetLocation(location) => {
+        println!("AppEvent::SetLocation({:#?})", location);
+        self.saved_location = Some(location.clone());
+        println!("New State: {:#?}", self);
+      }
+
+      AppEvent::SetLocations(locations) => {
+        println!("AppEvent::SetLocations({:#?})", locations);
+        self.locations = locations.clone();
+        if let Some(current) = self
+          .saved_location
+          .clone()
+        {
+          // pick up renames, and move on if the current location was deleted
+          self.saved_location = locations
+            .iter()
+            .find(|location| location.id == current.id)
+            .cloned();
+          if self
+            .saved_location
+            .is_none()
+          {
+            self.clear_forecast();
+            if let Some(first) = locations.first() {
+              ex.emit(AppEvent::SelectLocation(first.id));
+            }
+          }
+        }
+        println!("New State: {:#?}", self);
+      }
+
+      AppEvent::SelectLocation(id) => {
+        println!("AppEvent::SelectLocation({})", id);
+        let Some(location) = self
+          .locations
+          .iter()
+          .find(|location| location.id == *id)
+          .cloned()
+        else {
+          return;
+        };
+        self.saved_location = Some(location.clone());
+        self.clear_forecast();
+        self.screen = Screen::Forecast;
+        let repo = self
+          .repo
+          .clone();
+        run_in_background(ex, async move {
+          if let Err(e) = repo
+            .set_current_location(location.id)
+            .await
+          {
+            println!("failed to save the current location: {:?}", e);
+          }
+          let mut events = vec![];
+          if let Ok(hf) = repo
+            .get_latest_historical_forecast(location.id)
+            .await
+          {
+            events.push(AppEvent::Rehydrate(location, hf));
+          }
+          events.push(AppEvent::RefreshForecast);
+          events
+        });
+        println!("New State: {:#?}", self);
+      }
+
+      AppEvent::RenameLocation(id, name) => {
+        println!("AppEvent::RenameLocation({}, {:?})", id, name);
+        let (id, name) = (*id, name.clone());
+        let repo = self
+          .repo
+          .clone();
+        run_in_background(ex, async move {
+          if let Err(e) = repo
+            .rename_location(id, &name)
+            .await
+          {
+            println!("failed to rename location: {:?}", e);
+          }
+          reload_locations(&repo).await
+        });
+      }
+
+      AppEvent::DeleteLocation(id) => {
+        println!("AppEvent::DeleteLocation({})", id);
+        let id = *id;
+        let repo = self
+          .repo
+          .clone();
+        run_in_background(ex, async move {
+          if let Err(e) = repo
+            .delete_location(id)
+            .await
+          {
+            println!("failed to delete location: {:?}", e);
+          }
+          reload_locations(&repo).await
+        });
+      }
+
+      AppEvent::MoveLocation(from, to) => {
+        println!("AppEvent::MoveLocation({}, {})", from, to);
+        if *from
+          >= self
+            .locations
+            .len()
+          || *to
+            >= self
+              .locations
+              .len()
+        {
+          return;
+        }
+        let location = self
+          .locations
+          .remove(*from);
+        self
+          .locations
+          .insert(*to, location);
+        let order: Vec<i64> = self
+          .locations
+          .iter()
+          .map(|location| location.id)
+          .collect();
+        let repo = self
+          .repo
+          .clone();
+        run_in_background(ex, async move {
+          if let Err(e) = repo
+            .reorder_locations(&order)
+            .await
+          {
+            println!("failed to reorder locations: {:?}", e);
+          }
+          reload_locations(&repo).await
+        });
+        println!("New State: {:#?}", self);
+      }
+
+      AppEvent::SetUnits(prefs) => {
+        println!("AppEvent::SetUnits({:?})", prefs);
+        self.units = *prefs;
+        if let Some(meteo) = self
+          .weather_data
+          .as_mut()
+        {
+          meteo.convert_units(prefs);
+        }
+        println!("New State: {:#?}", self);
+      }
+
+      AppEvent::ChangeUnits(prefs) => {
+        println!("AppEvent::ChangeUnits({:?})", prefs);
+        let prefs = *prefs;
+        let repo = self
+          .repo
+          .clone();
+        run_in_background(ex, async move {
+          match repo
+            .save_unit_preferences(&prefs)
+            .await
+          {
+            Ok(()) => vec![AppEvent::SetUnits(prefs)],
+            Err(e) => {
+              println!("failed to save unit preferences: {:?}", e);
+              vec![]
+            }
+          }
+        });
+      }
+
+      AppEvent::SetForecastDays(days) => {
+        println!("AppEvent::SetForecastDays({})", days);
+        self.forecast_days = (*days).clamp(1, MAX_FORECAST_DAYS);
+        println!("New State: {:#?}", self);
+      }
+
+      AppEvent::ChangeForecastDays(days) => {
+        println!("AppEvent::ChangeForecastDays({})", days);
+        let days = (*days).clamp(1, MAX_FORECAST_DAYS);
+        let repo = self
+          .repo
+          .clone();
+        run_in_background(ex, async move {
+          match repo
+            .save_forecast_days(days)
+            .await
+          {
+            // the cached forecast covers the wrong number of days now
+            Ok(()) => vec![AppEvent::SetForecastDays(days), AppEvent::ForceRefresh],
+            Err(e) => {
+              println!("failed to save forecast days: {:?}", e);
+              vec![]
+            }
+          }
+        });
+      }
+
+      AppEvent::SetRetention(policy) => {
+        println!("AppEvent::SetRetention({:?})", policy);
+        self.retention = *policy;
+        println!("New State: {:#?}", self);
+      }
+
+      AppEvent::ChangeRetention(policy) => {
+        println!("AppEvent::ChangeRetention({:?})", policy);
+        let policy = *policy;
+        let repo = self
+          .repo
+          .clone();
+        run_in_background(ex, async move {
+          match repo
+            .save_retention_policy(&policy)
+            .await
+          {
+            Ok(()) => {
+              prune(&repo, &policy).await;
+              vec![AppEvent::SetRetention(policy)]
+            }
+            Err(e) => {
+              println!("failed to save retention policy: {:?}", e);
+              vec![]
+            }
+          }
+        });
+      }
+
+      AppEvent::CompactDatabase => {
+        println!("AppEvent::CompactDatabase");
+        self.maintenance_status = Some(String::from("Compacting…"));
+        let retention = self.retention;
+        let repo = self
+          .repo
+          .clone();
+        run_in_background(ex, async move {
+          let pruned = prune(&repo, &retention).await;
+          let status = match repo
+            .compact()
+            .await
+          {
+            Ok(()) => format!("Compacted, removed {} old forecasts", pruned),
+            Err(e) => format!("Couldn't compact the database: {}", e),
+          };
+          vec![AppEvent::DatabaseCompacted(status)]
+        });
+        println!("New State: {:#?}", self);
+      }
+
+      AppEvent::DatabaseCompacted(status) => {
+        println!("AppEvent::DatabaseCompacted({})", status);
+        self.maintenance_status = Some(status.clone());
+        println!("New State: {:#?}", self);
+      }
+
+      AppEvent::Navigate(screen) => {
+        println!("AppEvent::Navigate({:?})", screen);
+        self.screen = *screen;
         println!("New State: {:#?}", self);
       }
 
@@ -84,108 +662,307 @@ impl Model for AppData {
   }
 }
 
-fn handle_app_event_refresh_forecast(ex: &mut EventContext, app_data: AppData) -> Option<()> {
-  let (lat, lng) = app_data
-    .saved_location
-    .clone()?
-    .coords()?;
-  let api_response = get_weather_data(lat, lng)?;
-  let _ = add_forecast_to_db(&app_data.saved_location?, &api_response);
-  // ex.emit(AppEvent::Rehydrate(loc, ()));
-  Some(())
+impl AppData {
+  fn start_refresh(&mut self) -> u64 {
+    self.refresh_generation += 1;
+    self.loading = true;
+    self.refresh_generation
+  }
+
+  /// Whether the forecast on screen is recent enough that the provider can't have anything newer.
+  fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+    match (&self.saved_location, self.fetched_at, &self.weather_data) {
+      (Some(location), Some(fetched_at), Some(_)) => {
+        now - fetched_at
+          < location
+            .provider()
+            .update_interval()
+      }
+      _ => false,
+    }
+  }
+
+  /// Fetches a new forecast for the current location in the background. `false` when there's
+  /// no location to fetch for.
+  fn refresh(&mut self, ex: &mut EventContext) -> bool {
+    let location = self
+      .saved_location
+      .clone();
+    if let Some((location, (lat, lng))) = location.and_then(|location| {
+      location
+        .coords()
+        .map(|coords| (location, coords))
+    }) {
+      let generation = self.start_refresh();
+      let request = self.forecast_request(lat, lng, location.timezone());
+      let http = self
+        .http
+        .clone();
+      let repo = self
+        .repo
+        .clone();
+      let retention = self.retention;
+      run_in_background(ex, async move {
+        let result = refresh_forecast(&repo, location, http, request, retention).await;
+        vec![forecast_result(generation, result)]
+      });
+      true
+    } else {
+      false
+    }
+  }
+
+  /// Forgets the forecast on screen, along with any refresh still in flight for it.
+  fn clear_forecast(&mut self) {
+    self.weather_data = None;
+    self.fetched_at = None;
+    self.fetch_error = None;
+    self.loading = false;
+    self.refresh_generation += 1;
+  }
+
+  fn forecast_request(&self, lat: f64, lng: f64, timezone: &str) -> ForecastRequest {
+    ForecastRequest {
+      lat,
+      lng,
+      timezone: String::from(timezone),
+      units: self.units,
+      forecast_days: self.forecast_days,
+    }
+  }
 }
 
-const BASE_URL: &str = "https://api.open-meteo.com/v1/forecast";
-
-fn get_weather_data(lat: f64, lng: f64) -> Option<Meteo> {
-  let current_params: String = vec![
-    "temperature_2m",
-    "relative_humidity_2m",
-    "apparent_temperature",
-    "is_day",
-    "precipitation",
-    "rain",
-    "showers",
-    "snowfall",
-    "weather_code",
-    "cloud_cover",
-    "pressure_msl",
-    "surface_pressure",
-    "wind_speed_10m",
-    "wind_direction_10m",
-    "wind_gusts_10m",
-  ]
-  .join(",");
-
-  let hourly_params: String = vec![
-    "temperature_2m",
-    "relative_humidity_2m",
-    "dew_point_2m",
-    "precipitation_probability",
-    "precipitation",
-    "rain",
-    "showers",
-    "snowfall",
-    "weather_code",
-    "visibility",
-    "wind_gusts_10m",
-  ]
-  .join(",");
-
-  let daily_params: String = vec![
-    "weather_code",
-    "temperature_2m_max",
-    "temperature_2m_min",
-    "sunrise",
-    "sunset",
-    "daylight_duration",
-    "uv_index_max",
-    "precipitation_sum",
-    "rain_sum",
-    "snowfall_sum",
-    "precipitation_probability_max",
-  ]
-  .join(",");
-
-  let query = vec![
-    ("latitude", lat.to_string()),
-    ("longitude", lng.to_string()),
-    ("current", current_params),
-    ("hourly", hourly_params),
-    ("daily", daily_params),
-    ("temperature_unit", String::from("fahrenheit")),
-    ("wind_speed_unit", String::from("mph")),
-    ("precipitation_unit", String::from("inch")),
-    ("timezone", String::from("America/New_York")),
-    ("forecast_days", String::from("1")),
-    ("forecast_hours", String::from("24")),
-    ("past_hours", String::from("24")),
-  ];
-
-  Client::new()
-    .get(BASE_URL)
-    .query(&query)
-    .send()
-    .block_on()
-    .ok()?
-    .json::<Meteo>()
-    .block_on()
-    .ok()
+/// Fetches a fresh forecast for `location`, stores it, and prunes whatever history `retention`
+/// no longer keeps.
+async fn refresh_forecast(
+  repo: &Repository,
+  location: Location,
+  http: HttpClient,
+  request: ForecastRequest,
+  retention: RetentionPolicy,
+) -> Result<Meteo, FetchError> {
+  let api_response = location
+    .provider()
+    .fetch(&http, &request)
+    .await?;
+  store_forecast(repo, &location, &api_response).await;
+  prune(repo, &retention).await;
+  Ok(api_response)
 }
 
-pub async fn rehydrate_from_db(cx: &mut Context) -> anyhow::Result<()> {
+/// Fetches forecasts for every location in `requests` that doesn't have a fresh one stored yet,
+/// all the Open-Meteo ones in a single request, and stores them.
+async fn refresh_all(
+  repo: &Repository,
+  http: &HttpClient,
+  requests: Vec<(Location, ForecastRequest)>,
+  retention: RetentionPolicy,
+) {
+  let mut stale = vec![];
+  for (location, request) in requests {
+    let fresh = repo
+      .get_latest_historical_forecast(location.id)
+      .await
+      .is_ok_and(|hf| {
+        Utc::now() - hf.timestamp
+          < location
+            .provider()
+            .update_interval()
+      });
+    if !fresh {
+      stale.push((location, request));
+    }
+  }
+
+  let (batched, single): (Vec<_>, Vec<_>) = stale
+    .into_iter()
+    .partition(|(location, _)| location.provider() == ProviderKind::OpenMeteo);
+  if !batched.is_empty() {
+    let requests: Vec<_> = batched
+      .iter()
+      .map(|(_, request)| request.clone())
+      .collect();
+    match OpenMeteo::new(open_meteo::BASE_URL, http.clone())
+      .fetch_many(&requests)
+      .await
+    {
+      Ok(forecasts) => {
+        for ((location, _), meteo) in batched
+          .iter()
+          .zip(forecasts)
+        {
+          store_forecast(repo, location, &meteo).await;
+        }
+      }
+      Err(e) => println!("failed to refresh {} locations: {}", batched.len(), e),
+    }
+  }
+  // the other providers only take one place at a time
+  for (location, request) in single {
+    match location
+      .provider()
+      .fetch(http, &request)
+      .await
+    {
+      Ok(meteo) => store_forecast(repo, &location, &meteo).await,
+      Err(e) => println!("failed to refresh {}: {}", location.name, e),
+    }
+  }
+  prune(repo, &retention).await;
+}
+
+/// Saves a fetched forecast to `location`'s history, and its timezone if it didn't have one and
+/// the provider resolved it.
+async fn store_forecast(repo: &Repository, location: &Location, meteo: &Meteo) {
+  if location
+    .timezone
+    .is_none()
+    && location
+      .provider()
+      .resolves_timezone()
+  {
+    let _ = repo
+      .set_location_timezone(&location.geohash, &meteo.timezone)
+      .await;
+  }
+  let _ = repo
+    .add_forecast_to_db(location, meteo, Utc::now())
+    .await;
+}
+
+async fn prune(repo: &Repository, retention: &RetentionPolicy) -> u64 {
+  match repo
+    .prune(retention, Utc::now())
+    .await
+  {
+    Ok(pruned) => {
+      if pruned > 0 {
+        println!("pruned {} old forecasts", pruned);
+      }
+      pruned
+    }
+    Err(e) => {
+      println!("failed to prune old forecasts: {:?}", e);
+      0
+    }
+  }
+}
+
+async fn reload_locations(repo: &Repository) -> Vec<AppEvent> {
+  match repo
+    .get_all_locations()
+    .await
+  {
+    Ok(locations) => vec![AppEvent::SetLocations(locations)],
+    Err(e) => {
+      println!("failed to load locations: {:?}", e);
+      vec![]
+    }
+  }
+}
+
+fn forecast_result(generation: u64, result: Result<Meteo, FetchError>) -> AppEvent {
+  match result {
+    Ok(meteo) => AppEvent::ForecastLoaded(generation, meteo),
+    Err(error) => AppEvent::FailedToRetrieveForecast(generation, error),
+  }
+}
+
+/// Runs `task` on the tokio runtime instead of the UI thread, then posts the events it resolves
+/// to back to whoever spawned it.
+pub fn run_in_background<E, F>(ex: &mut EventContext, task: F)
+where
+  E: Send + 'static,
+  F: Future<Output = Vec<E>> + Send + 'static,
+{
+  post_when_done(ex.get_proxy(), task);
+}
+
+/// `run_in_background` for when there's only a proxy to post through, like while the app is
+/// still being built.
+pub fn post_when_done<E, F>(mut proxy: ContextProxy, task: F)
+where
+  E: Send + 'static,
+  F: Future<Output = Vec<E>> + Send + 'static,
+{
+  Handle::current().spawn(async move {
+    for event in task.await {
+      if let Err(e) = proxy.emit(event) {
+        println!("failed to post background result: {:?}", e);
+      }
+    }
+  });
+}
+
+/// Loads the settings and the current location's last forecast, as the events that put them on
+/// screen.
+pub async fn rehydrate_from_db(repo: Repository) -> Vec<AppEvent> {
   println!("Rehydrating 🚰");
-  let saved_location = get_latest_location().await?;
-  let historical_forecast = get_latest_historical_forecast(saved_location.id).await;
+  let mut events = vec![];
+  match repo
+    .get_unit_preferences()
+    .await
+  {
+    Ok(prefs) => events.push(AppEvent::SetUnits(prefs)),
+    Err(e) => println!("{:?}", e),
+  }
+  match repo
+    .get_forecast_days()
+    .await
+  {
+    Ok(days) => events.push(AppEvent::SetForecastDays(days)),
+    Err(e) => println!("{:?}", e),
+  }
+  match repo
+    .get_retention_policy()
+    .await
+  {
+    Ok(policy) => events.push(AppEvent::SetRetention(policy)),
+    Err(e) => println!("{:?}", e),
+  }
+  match repo
+    .get_refresh_schedule()
+    .await
+  {
+    Ok(schedule) => events.push(AppEvent::SetRefreshSchedule(schedule)),
+    Err(e) => println!("{:?}", e),
+  }
+  match repo
+    .get_dashboard_cells()
+    .await
+  {
+    Ok(cells) => events.push(AppEvent::SetDashboardCells(cells)),
+    Err(e) => println!("{:?}", e),
+  }
+  let saved_location = match repo
+    .get_current_location()
+    .await
+  {
+    Ok(location) => location,
+    Err(e) => {
+      println!("{:?}", e);
+      return events;
+    }
+  };
+  let historical_forecast = repo
+    .get_latest_historical_forecast(saved_location.id)
+    .await;
   match historical_forecast {
     Ok(hf) => {
-      cx.emit(AppEvent::Rehydrate(saved_location, hf));
+      events.push(AppEvent::Rehydrate(saved_location, hf));
     }
     Err(e) => {
       println!("{:?}", e);
+      events.push(AppEvent::SetLocation(saved_location));
     }
   }
-  cx.emit(AppEvent::RefreshForecast);
-
-  Ok(())
+  match repo
+    .get_all_locations()
+    .await
+  {
+    Ok(locations) => events.push(AppEvent::SetLocations(locations)),
+    Err(e) => println!("{:?}", e),
+  }
+  events.push(AppEvent::RefreshForecast);
+  events
 }
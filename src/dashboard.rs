@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use vizia::prelude::*;
+
+use crate::api_models::Current;
+use crate::app_data::AppData;
+use crate::views::DataCell;
+
+/// A reading the current-conditions dashboard can show.
+#[derive(Copy, Clone, Data, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DashboardCell {
+  Temperature,
+  ApparentTemperature,
+  Humidity,
+  Precipitation,
+  Rain,
+  Showers,
+  Snowfall,
+  CloudCover,
+  Pressure,
+  SurfacePressure,
+  WindSpeed,
+  WindDirection,
+  WindGusts,
+}
+
+impl DashboardCell {
+  pub const ALL: [DashboardCell; 13] = [
+    DashboardCell::Temperature,
+    DashboardCell::ApparentTemperature,
+    DashboardCell::Humidity,
+    DashboardCell::Precipitation,
+    DashboardCell::Rain,
+    DashboardCell::Showers,
+    DashboardCell::Snowfall,
+    DashboardCell::CloudCover,
+    DashboardCell::Pressure,
+    DashboardCell::SurfacePressure,
+    DashboardCell::WindSpeed,
+    DashboardCell::WindDirection,
+    DashboardCell::WindGusts,
+  ];
+
+  /// What the dashboard shows until it's been configured.
+  pub const DEFAULT: [DashboardCell; 8] = [
+    DashboardCell::Temperature,
+    DashboardCell::ApparentTemperature,
+    DashboardCell::Humidity,
+    DashboardCell::Pressure,
+    DashboardCell::CloudCover,
+    DashboardCell::WindSpeed,
+    DashboardCell::WindDirection,
+    DashboardCell::WindGusts,
+  ];
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      DashboardCell::Temperature => "Temperature",
+      DashboardCell::ApparentTemperature => "Feels like",
+      DashboardCell::Humidity => "Humidity",
+      DashboardCell::Precipitation => "Precipitation",
+      DashboardCell::Rain => "Rain",
+      DashboardCell::Showers => "Showers",
+      DashboardCell::Snowfall => "Snowfall",
+      DashboardCell::CloudCover => "Cloud cover",
+      DashboardCell::Pressure => "Pressure",
+      DashboardCell::SurfacePressure => "Surface pressure",
+      DashboardCell::WindSpeed => "Wind",
+      DashboardCell::WindDirection => "Wind direction",
+      DashboardCell::WindGusts => "Gusts",
+    }
+  }
+
+  /// The Open-Meteo variable the cell reads, as named in `Current::variables`.
+  pub fn variable(&self) -> &'static str {
+    match self {
+      DashboardCell::Temperature => "temperature_2m",
+      DashboardCell::ApparentTemperature => "apparent_temperature",
+      DashboardCell::Humidity => "relative_humidity_2m",
+      DashboardCell::Precipitation => "precipitation",
+      DashboardCell::Rain => "rain",
+      DashboardCell::Showers => "showers",
+      DashboardCell::Snowfall => "snowfall",
+      DashboardCell::CloudCover => "cloud_cover",
+      DashboardCell::Pressure => "pressure_msl",
+      DashboardCell::SurfacePressure => "surface_pressure",
+      DashboardCell::WindSpeed => "wind_speed_10m",
+      DashboardCell::WindDirection => "wind_direction_10m",
+      DashboardCell::WindGusts => "wind_gusts_10m",
+    }
+  }
+
+  pub fn value(&self, current: &Current) -> Option<f64> {
+    current
+      .variables()
+      .into_iter()
+      .find(|(name, _)| *name == self.variable())
+      .and_then(|(_, values)| {
+        values
+          .first()
+          .copied()
+          .flatten()
+      })
+  }
+}
+
+/// The current conditions as a grid of `DataCell`s, one for each of `AppData::dashboard_cells`.
+pub struct Dashboard {}
+
+impl Dashboard {
+  /// Cells per row. They stretch to share the width between them.
+  const COLUMNS: usize = 4;
+
+  pub fn new(cx: &mut Context) -> Handle<Self> {
+    Self {}.build(cx, |cx| {
+      Binding::new(cx, AppData::dashboard_cells, |cx, cells| {
+        let cells = cells.get(cx);
+        Binding::new(cx, AppData::weather_data, move |cx, lens| {
+          let Some(forecast) = lens.get(cx) else {
+            return;
+          };
+          VStack::new(cx, |cx| {
+            for row in cells.chunks(Self::COLUMNS) {
+              HStack::new(cx, |cx| {
+                for cell in row {
+                  let unit = forecast
+                    .current_units
+                    .unit(cell.variable())
+                    .unwrap_or_default();
+                  DataCell::new(
+                    cx,
+                    String::from(cell.label()),
+                    cell.value(&forecast.current),
+                    String::from(unit),
+                  )
+                  .class("dashboard_cell");
+                }
+              })
+              .class("row");
+            }
+          })
+          .class("dashboard");
+        });
+      });
+    })
+  }
+}
+
+impl View for Dashboard {}
@@ -0,0 +1,259 @@
+use std::f32::consts::PI;
+
+use chrono::{DateTime, NaiveDateTime, Timelike, Utc};
+use vizia::prelude::*;
+use vizia::vg;
+
+use crate::api_models::Meteo;
+use crate::app_data::AppData;
+use crate::providers::TIME_FORMAT;
+
+/// Where the sun is relative to today's sunrise and sunset.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Sun {
+  BeforeSunrise,
+  /// How far across the sky it's come, from 0 at sunrise to 1 at sunset.
+  Up(f64),
+  AfterSunset,
+  /// It doesn't set today. Carries how far through the day it is instead.
+  PolarDay(f64),
+  /// It doesn't rise today.
+  PolarNight,
+}
+
+/// Today's daylight at the forecast's location, all in its local time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Daylight {
+  pub sunrise: Option<NaiveDateTime>,
+  pub sunset: Option<NaiveDateTime>,
+  /// Seconds of daylight today.
+  pub duration: Option<f64>,
+  /// Seconds more (or less, when negative) than the day before.
+  pub change: Option<f64>,
+  pub sun: Option<Sun>,
+}
+
+impl Daylight {
+  /// Reads the day `now` falls on in the forecast's timezone out of the daily series. `None`
+  /// when that day isn't in it.
+  pub fn from_meteo(forecast: &Meteo, now: DateTime<Utc>) -> Option<Self> {
+    let daily = &forecast.daily;
+    let day = forecast.index_of_now(&daily.time, now);
+    let now = forecast.local_time(now);
+    if daily
+      .time
+      .get(day)
+      != Some(
+        &now
+          .format("%Y-%m-%d")
+          .to_string(),
+      )
+    {
+      return None;
+    }
+    let time = |times: &[String]| {
+      times
+        .get(day)
+        .and_then(|time| NaiveDateTime::parse_from_str(time, TIME_FORMAT).ok())
+    };
+    let sunrise = time(&daily.sunrise);
+    let sunset = time(&daily.sunset);
+    let duration_on = |day: usize| {
+      daily
+        .daylight_duration
+        .get(day)
+        .copied()
+        .flatten()
+    };
+    let duration = duration_on(day).or_else(|| {
+      sunrise
+        .zip(sunset)
+        .map(|(sunrise, sunset)| (sunset - sunrise).num_seconds() as f64)
+    });
+    let change = day
+      .checked_sub(1)
+      .and_then(duration_on)
+      .zip(duration)
+      .map(|(yesterday, today)| today - yesterday);
+
+    let sun = match duration {
+      // at the poles the sunrise and sunset Open-Meteo sends can't be trusted, the duration can
+      Some(seconds) if seconds <= 0.0 => Some(Sun::PolarNight),
+      Some(seconds) if seconds >= 86_400.0 => Some(Sun::PolarDay(
+        now.num_seconds_from_midnight() as f64 / 86_400.0,
+      )),
+      _ => match (sunrise, sunset) {
+        (Some(sunrise), Some(sunset)) if sunrise < sunset => Some(if now < sunrise {
+          Sun::BeforeSunrise
+        } else if now >= sunset {
+          Sun::AfterSunset
+        } else {
+          Sun::Up((now - sunrise).num_seconds() as f64 / (sunset - sunrise).num_seconds() as f64)
+        }),
+        _ => None,
+      },
+    };
+
+    Some(Self {
+      sunrise,
+      sunset,
+      duration,
+      change,
+      sun,
+    })
+  }
+}
+
+/// A duration in seconds as hours and minutes, e.g. "12h 08m".
+pub fn format_daylight(seconds: f64) -> String {
+  let minutes = (seconds.max(0.0) / 60.0).round() as u64;
+  format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+/// How the day's length compares with the day before, e.g. "3m 38s less than yesterday".
+pub fn describe_change(seconds: f64) -> String {
+  let total = seconds
+    .abs()
+    .round() as u64;
+  if total == 0 {
+    return String::from("the same as yesterday");
+  }
+  let amount = if total >= 60 {
+    format!("{}m {:02}s", total / 60, total % 60)
+  } else {
+    format!("{}s", total)
+  };
+  if seconds > 0.0 {
+    format!("{} more than yesterday", amount)
+  } else {
+    format!("{} less than yesterday", amount)
+  }
+}
+
+/// The sun's path from sunrise to sunset as an arc over the horizon, with where it is now, how
+/// long the day is, and how that's changing.
+pub struct DaylightArc {}
+
+impl DaylightArc {
+  pub fn new(cx: &mut Context, forecast: &Meteo) -> Handle<Self> {
+    let forecast = forecast.clone();
+    Self {}.build(cx, |cx| {
+      // follows the clock, not the forecast, so a cached one doesn't freeze the sun in place
+      Binding::new(cx, AppData::now, move |cx, now| {
+        let Some(daylight) = Daylight::from_meteo(&forecast, now.get(cx)) else {
+          return;
+        };
+        let zone = &forecast.timezone_abbreviation;
+        HStack::new(cx, |cx| {
+          ArcCanvas { sun: daylight.sun }
+            .build(cx, |_| {})
+            .class("daylight_arc");
+          VStack::new(cx, |cx| {
+            match daylight.sun {
+              Some(Sun::PolarDay(_)) => {
+                Label::new(cx, "The sun doesn't set today");
+              }
+              Some(Sun::PolarNight) => {
+                Label::new(cx, "The sun doesn't rise today");
+              }
+              _ => {
+                let time = |time: Option<NaiveDateTime>| {
+                  time.map_or(String::from("--:--"), |time| {
+                    time
+                      .format("%H:%M")
+                      .to_string()
+                  })
+                };
+                Label::new(
+                  cx,
+                  format!(
+                    "Sunrise {}, sunset {} {}",
+                    time(daylight.sunrise),
+                    time(daylight.sunset),
+                    zone
+                  ),
+                );
+              }
+            }
+            if let Some(duration) = daylight.duration {
+              Label::new(cx, format!("{} of daylight", format_daylight(duration)));
+            }
+            if let Some(change) = daylight.change {
+              Label::new(cx, describe_change(change));
+            }
+          });
+        })
+        .class("row");
+      });
+    })
+  }
+}
+
+impl View for DaylightArc {}
+
+struct ArcCanvas {
+  sun: Option<Sun>,
+}
+
+impl View for ArcCanvas {
+  fn draw(&self, cx: &mut DrawContext, canvas: &vg::Canvas) {
+    let bounds = cx.bounds();
+    let scale = cx.scale_factor();
+    let pad = 8.0 * scale;
+    let radius = (bounds.w / 2.0).min(bounds.h) - pad;
+    let (center_x, horizon) = (bounds.x + bounds.w / 2.0, bounds.y + bounds.h - pad / 2.0);
+    let oval = vg::Rect::from_xywh(
+      center_x - radius,
+      horizon - radius,
+      radius * 2.0,
+      radius * 2.0,
+    );
+    // 0 is sunrise on the left, 1 sunset on the right
+    let point = |progress: f32| {
+      (
+        center_x - radius * (PI * progress).cos(),
+        horizon - radius * (PI * progress).sin(),
+      )
+    };
+
+    let mut stroke = vg::Paint::default();
+    stroke.set_anti_alias(true);
+    stroke.set_style(vg::PaintStyle::Stroke);
+    stroke.set_stroke_width(1.0 * scale);
+    stroke.set_color(vg::Color::from_argb(255, 168, 153, 132));
+    let mut ground = vg::Path::new();
+    ground.move_to((bounds.x, horizon));
+    ground.line_to((bounds.x + bounds.w, horizon));
+    canvas.draw_path(&ground, &stroke);
+
+    if self.sun == Some(Sun::PolarNight) {
+      stroke.set_color(vg::Color::from_argb(96, 168, 153, 132));
+    }
+    let mut sky = vg::Path::new();
+    sky.add_arc(&oval, 180.0, 180.0);
+    canvas.draw_path(&sky, &stroke);
+
+    let mut sun = vg::Paint::default();
+    sun.set_anti_alias(true);
+    sun.set_color(vg::Color::from_argb(255, 250, 189, 47));
+    let (progress, up) = match self.sun {
+      Some(Sun::Up(progress)) | Some(Sun::PolarDay(progress)) => (progress as f32, true),
+      Some(Sun::BeforeSunrise) => (0.0, false),
+      Some(Sun::AfterSunset) => (1.0, false),
+      Some(Sun::PolarNight) | None => return,
+    };
+    let progress = progress.clamp(0.0, 1.0);
+    if up {
+      // the part of the day that's gone already
+      let mut trail = stroke.clone();
+      trail.set_stroke_width(3.0 * scale);
+      trail.set_color(vg::Color::from_argb(255, 250, 189, 47));
+      let mut path = vg::Path::new();
+      path.add_arc(&oval, 180.0, 180.0 * progress);
+      canvas.draw_path(&path, &trail);
+    } else {
+      sun.set_color(vg::Color::from_argb(128, 250, 189, 47));
+    }
+    canvas.draw_circle(point(progress), 5.0 * scale, &sun);
+  }
+}
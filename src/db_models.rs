@@ -1,28 +1,119 @@
+use chrono::{DateTime, NaiveDate, SecondsFormat, TimeDelta, Utc};
 use geohash;
+use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use vizia::binding::Data;
 use vizia::prelude::*;
 
-#[derive(Lens, FromRow, Data, Debug, Default, Clone)]
+use crate::api_models::Meteo;
+use crate::providers::ProviderKind;
+
+pub const AUTO_TIMEZONE: &str = "auto";
+
+#[derive(Lens, FromRow, Data, Debug, Default, Clone, PartialEq)]
 pub struct Location {
   pub id: i64,
   pub name: String,
   pub geohash: String,
+  pub timezone: Option<String>,
+  pub provider: String,
+  /// Where the location sits in the locations list, lowest first.
+  pub position: i64,
 }
 
 impl Location {
+  /// The IANA timezone forecasts for this location are requested in.
+  /// Open-Meteo resolves `auto` from the coordinates until we've stored one.
+  pub fn timezone(&self) -> &str {
+    self
+      .timezone
+      .as_deref()
+      .unwrap_or(AUTO_TIMEZONE)
+  }
+
+  pub fn provider(&self) -> ProviderKind {
+    ProviderKind::from_id(&self.provider).unwrap_or_default()
+  }
+
   pub fn coords(&self) -> Option<(f64, f64)> {
     let (_, x, y) = geohash::decode(&self.geohash).ok()?;
     Some((y, x))
   }
 }
 
-#[derive(Lens, FromRow, Data, Debug, Default, Clone)]
+#[derive(Lens, FromRow, Debug, Default, Clone)]
 pub struct HistoricalForecast {
   pub id: i64,
   pub location_id: i64,
   pub response: String,
-  pub timestamp: String,
-  // pub timestamp: DateTime<Utc>,
+  /// When the forecast was fetched.
+  pub timestamp: DateTime<Utc>,
   // TODO: figure out the idiomatic way to handle foreign key location_id
 }
+
+impl HistoricalForecast {
+  pub fn meteo(&self) -> anyhow::Result<Meteo> {
+    Ok(serde_json::from_str(&self.response)?)
+  }
+}
+
+/// One hourly variable at one hour, as fetched at `fetched_at`.
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct HourlyValue {
+  pub forecast_id: i64,
+  pub location_id: i64,
+  pub fetched_at: DateTime<Utc>,
+  pub valid_time: DateTime<Utc>,
+  pub variable: String,
+  pub value: Option<f64>,
+  pub unit: String,
+}
+
+/// One daily variable on one local calendar day, as fetched at `fetched_at`.
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct DailyValue {
+  pub forecast_id: i64,
+  pub location_id: i64,
+  pub fetched_at: DateTime<Utc>,
+  pub valid_date: NaiveDate,
+  pub variable: String,
+  pub value: Option<f64>,
+  pub unit: String,
+}
+
+/// How much forecast history to keep. Every fetch is kept for `keep_all_days`, then only the
+/// last fetch of each day per location, until `keep_daily_days` (if set) have passed.
+#[derive(Copy, Clone, Data, Lens, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+  pub keep_all_days: u32,
+  pub keep_daily_days: Option<u32>,
+}
+
+impl Default for RetentionPolicy {
+  fn default() -> Self {
+    Self {
+      keep_all_days: 7,
+      keep_daily_days: None,
+    }
+  }
+}
+
+impl RetentionPolicy {
+  pub const DAILY_CHOICES: [Option<u32>; 4] = [Some(30), Some(90), Some(365), None];
+
+  /// Fetches older than the first cutoff are thinned to one a day, and older than the second
+  /// are dropped.
+  pub fn cutoffs(&self, now: DateTime<Utc>) -> (DateTime<Utc>, Option<DateTime<Utc>>) {
+    let keep_all = now - TimeDelta::days(self.keep_all_days as i64);
+    let keep_daily = self
+      .keep_daily_days
+      .map(|days| now - TimeDelta::days(days.max(self.keep_all_days) as i64));
+    (keep_all, keep_daily)
+  }
+}
+
+/// How timestamps are written to the database: RFC 3339 in UTC with millisecond precision, so
+/// that sorting the text sorts by time.
+pub fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+  timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
@@ -0,0 +1,311 @@
+use chrono::{DateTime, Utc};
+use vizia::prelude::*;
+use vizia::vg;
+
+use crate::api_models::Meteo;
+use crate::app_data::AppData;
+use crate::views::format_value;
+
+/// The numbers behind the hourly chart, laid out one column per hour.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChartData {
+  pub times: Vec<String>,
+  pub temperature: Vec<Option<f64>>,
+  pub temperature_unit: String,
+  pub probability: Vec<Option<f64>>,
+  pub precipitation: Vec<Option<f64>>,
+  pub precipitation_unit: String,
+  /// Where the wall clock falls, in hours from the start of the series.
+  pub now: Option<f64>,
+}
+
+impl ChartData {
+  /// How wide each hour is drawn, in pixels.
+  pub const HOUR_WIDTH: f32 = 40.0;
+
+  pub fn from_meteo(forecast: &Meteo, now: DateTime<Utc>) -> Self {
+    let hourly = &forecast.hourly;
+    let start = hourly
+      .time
+      .first()
+      .and_then(|time| forecast.parse_time(time));
+    Self {
+      times: hourly
+        .time
+        .clone(),
+      temperature: hourly
+        .temperature_2_m
+        .clone(),
+      temperature_unit: forecast
+        .hourly_units
+        .temperature_2_m
+        .clone(),
+      probability: hourly
+        .precipitation_probability
+        .clone(),
+      precipitation: hourly
+        .precipitation
+        .clone(),
+      precipitation_unit: forecast
+        .hourly_units
+        .precipitation
+        .clone(),
+      now: start.map(|start| (now - start.with_timezone(&Utc)).num_minutes() as f64 / 60.0),
+    }
+  }
+
+  pub fn hours(&self) -> usize {
+    self
+      .times
+      .len()
+  }
+
+  pub fn width(&self) -> f32 {
+    self.hours() as f32 * Self::HOUR_WIDTH
+  }
+
+  /// The middle of `hour`'s column.
+  pub fn x(&self, hour: f64) -> f32 {
+    (hour as f32 + 0.5) * Self::HOUR_WIDTH
+  }
+
+  /// Which hour's column `x` is over, if any.
+  pub fn hour_at(&self, x: f32) -> Option<usize> {
+    if x < 0.0 {
+      return None;
+    }
+    let hour = (x / Self::HOUR_WIDTH) as usize;
+    (hour < self.hours()).then_some(hour)
+  }
+
+  /// The lowest and highest temperature, spread apart if they're the same so there's a line to
+  /// draw.
+  pub fn temperature_range(&self) -> Option<(f64, f64)> {
+    let values = self
+      .temperature
+      .iter()
+      .flatten();
+    let low = values
+      .clone()
+      .copied()
+      .reduce(f64::min)?;
+    let high = values
+      .copied()
+      .reduce(f64::max)?;
+    if high - low < 1.0 {
+      Some((low - 0.5, high + 0.5))
+    } else {
+      Some((low, high))
+    }
+  }
+
+  /// Exact values for `hour`, for the tooltip.
+  pub fn describe(&self, hour: usize) -> String {
+    let value = |values: &[Option<f64>]| {
+      values
+        .get(hour)
+        .copied()
+        .flatten()
+    };
+    let time = self
+      .times
+      .get(hour)
+      .and_then(|time| time.get(11..16))
+      .unwrap_or_default();
+    format!(
+      "{}  {}  {} chance  {}",
+      time,
+      format_value(value(&self.temperature), &self.temperature_unit),
+      format_value(value(&self.probability), "%"),
+      format_value(value(&self.precipitation), &self.precipitation_unit),
+    )
+  }
+}
+
+enum ChartEvent {
+  Hover(Option<usize>),
+}
+
+#[derive(Default, Debug, Clone, Lens)]
+struct ChartState {
+  hovered: Option<usize>,
+}
+
+impl Model for ChartState {
+  fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+    event.map(|chart_event, _meta| match chart_event {
+      ChartEvent::Hover(hour) => {
+        self.hovered = *hour;
+      }
+    });
+  }
+}
+
+/// Temperature as a line over bars of precipitation chance, with the amount that falls drawn
+/// darker inside them. Scrolls sideways when the hours don't fit.
+pub struct HourlyChart {}
+
+impl HourlyChart {
+  pub fn new(cx: &mut Context, forecast: &Meteo) -> Handle<Self> {
+    Self {}.build(cx, |cx| {
+      ChartState::default().build(cx);
+      ScrollView::new(cx, |cx| {
+        ZStack::new(cx, |cx| {
+          // rebuilt as the clock ticks so the "now" line keeps up, even on a cached forecast,
+          // and the tooltip reads from the same data the canvas draws
+          let forecast = forecast.clone();
+          Binding::new(cx, AppData::now, move |cx, now| {
+            let data = ChartData::from_meteo(&forecast, now.get(cx));
+            ChartCanvas { data: data.clone() }
+              .build(cx, |_| {})
+              .width(Pixels(data.width()))
+              .height(Stretch(1.0));
+            Binding::new(cx, ChartState::hovered, move |cx, lens| {
+              if let Some(hour) = lens.get(cx) {
+                Label::new(cx, data.describe(hour))
+                  .class("chart_tooltip")
+                  .position_type(PositionType::SelfDirected)
+                  .left(Pixels(
+                    // keep it inside the chart near the right-hand edge
+                    (hour as f32 * ChartData::HOUR_WIDTH)
+                      .min(data.width() - TOOLTIP_WIDTH)
+                      .max(0.0),
+                  ))
+                  .top(Pixels(0.0));
+              }
+            });
+          });
+        })
+        .width(Auto)
+        .on_hover_out(|ex| ex.emit(ChartEvent::Hover(None)));
+      })
+      .show_horizontal_scrollbar(true)
+      .show_vertical_scrollbar(false);
+    })
+  }
+}
+
+impl View for HourlyChart {
+  fn element(&self) -> Option<&'static str> {
+    Some("hourlychart")
+  }
+}
+
+const TOOLTIP_WIDTH: f32 = 240.0;
+
+struct ChartCanvas {
+  data: ChartData,
+}
+
+impl View for ChartCanvas {
+  fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+    event.map(|window_event, _meta| {
+      if let WindowEvent::MouseMove(x, _y) = window_event {
+        // the position is in physical pixels, the columns are laid out in logical ones
+        let left = cx
+          .bounds()
+          .x;
+        let hour = self
+          .data
+          .hour_at((*x - left) / cx.scale_factor());
+        cx.emit(ChartEvent::Hover(hour));
+      }
+    });
+  }
+
+  fn draw(&self, cx: &mut DrawContext, canvas: &vg::Canvas) {
+    let bounds = cx.bounds();
+    let scale = cx.scale_factor();
+    let data = &self.data;
+    let x = |hour: f64| bounds.x + data.x(hour) * scale;
+    // leave room at the top for the tooltip
+    let top = bounds.y + 24.0 * scale;
+    let height = bounds.h - 24.0 * scale;
+    let bottom = top + height;
+
+    let mut bars = vg::Paint::default();
+    bars.set_anti_alias(true);
+    bars.set_color(vg::Color::from_argb(255, 69, 133, 136));
+    let mut amount = bars.clone();
+    amount.set_color(vg::Color::from_argb(255, 131, 165, 152));
+    let bar_width = ChartData::HOUR_WIDTH * 0.6 * scale;
+    for hour in 0..data.hours() {
+      let left = x(hour as f64) - bar_width / 2.0;
+      if let Some(probability) = data
+        .probability
+        .get(hour)
+        .copied()
+        .flatten()
+      {
+        let bar = height * (probability.clamp(0.0, 100.0) / 100.0) as f32;
+        canvas.draw_rect(
+          vg::Rect::from_xywh(left, bottom - bar, bar_width, bar),
+          &bars,
+        );
+      }
+      // scaled against 10mm or 0.4in an hour, which is a downpour either way
+      if let Some(precipitation) = data
+        .precipitation
+        .get(hour)
+        .copied()
+        .flatten()
+      {
+        let downpour = if data.precipitation_unit == "inch" {
+          0.4
+        } else {
+          10.0
+        };
+        let bar = height * (precipitation / downpour).clamp(0.0, 1.0) as f32;
+        canvas.draw_rect(
+          vg::Rect::from_xywh(left, bottom - bar, bar_width, bar),
+          &amount,
+        );
+      }
+    }
+
+    if let Some((low, high)) = data.temperature_range() {
+      let y =
+        |value: f64| bottom - height * 0.1 - (height * 0.8) * ((value - low) / (high - low)) as f32;
+      let mut line = vg::Paint::default();
+      line.set_anti_alias(true);
+      line.set_style(vg::PaintStyle::Stroke);
+      line.set_stroke_width(2.0 * scale);
+      line.set_color(vg::Color::from_argb(255, 250, 189, 47));
+      let mut path = vg::Path::new();
+      let mut drawing = false;
+      for (hour, value) in data
+        .temperature
+        .iter()
+        .enumerate()
+      {
+        // gaps in the model's data are gaps in the line
+        match value {
+          Some(value) if drawing => {
+            path.line_to((x(hour as f64), y(*value)));
+          }
+          Some(value) => {
+            path.move_to((x(hour as f64), y(*value)));
+            drawing = true;
+          }
+          None => drawing = false,
+        }
+      }
+      canvas.draw_path(&path, &line);
+    }
+
+    if let Some(now) = data
+      .now
+      .filter(|now| (0.0..data.hours() as f64).contains(now))
+    {
+      let mut marker = vg::Paint::default();
+      marker.set_anti_alias(true);
+      marker.set_style(vg::PaintStyle::Stroke);
+      marker.set_stroke_width(1.0 * scale);
+      marker.set_color(vg::Color::from_argb(255, 251, 73, 52));
+      let mut path = vg::Path::new();
+      path.move_to((x(now), top));
+      path.line_to((x(now), bottom));
+      canvas.draw_path(&path, &marker);
+    }
+  }
+}
@@ -0,0 +1,217 @@
+use std::path::{Path, PathBuf};
+use std::{env, fmt, fs};
+
+use reqwest::header::{ACCEPT, RETRY_AFTER};
+use reqwest::{StatusCode, Url};
+use serde::de::DeserializeOwned;
+use vizia::prelude::*;
+
+// NOTE: both MET Norway and the NWS turn away requests without an identifying user agent
+const USER_AGENT: &str = concat!(
+  "rain/",
+  env!("CARGO_PKG_VERSION"),
+  " (https://github.com/zachcoyle/rain)"
+);
+
+/// Set to `record:<dir>` or `replay:<dir>` to capture or serve responses from disk.
+pub const HTTP_MODE_VAR: &str = "RAIN_HTTP_MODE";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum HttpMode {
+  #[default]
+  Live,
+  /// Go to the network and save every response body under the directory.
+  Record(PathBuf),
+  /// Never touch the network; answer from responses saved under the directory.
+  Replay(PathBuf),
+}
+
+impl HttpMode {
+  pub fn from_env() -> Self {
+    let Ok(mode) = env::var(HTTP_MODE_VAR) else {
+      return HttpMode::Live;
+    };
+    match mode.split_once(':') {
+      Some(("record", dir)) => HttpMode::Record(PathBuf::from(dir)),
+      Some(("replay", dir)) => HttpMode::Replay(PathBuf::from(dir)),
+      _ => HttpMode::Live,
+    }
+  }
+}
+
+/// Why a forecast couldn't be fetched.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FetchError {
+  /// No response at all: offline, DNS, TLS, timeouts, or a recorded response that couldn't be
+  /// read or written.
+  Network(String),
+  /// The server answered with an error status.
+  Status(u16),
+  /// The server asked us to back off, for this many seconds if it said.
+  RateLimited(Option<u64>),
+  /// The response didn't look like a forecast.
+  Parse(String),
+}
+
+impl fmt::Display for FetchError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      FetchError::Network(reason) => write!(f, "Couldn't reach the weather service: {}", reason),
+      FetchError::Status(status) => write!(f, "The weather service returned HTTP {}", status),
+      FetchError::RateLimited(Some(seconds)) => {
+        write!(f, "Too many requests, try again in {} seconds", seconds)
+      }
+      FetchError::RateLimited(None) => write!(f, "Too many requests, try again later"),
+      FetchError::Parse(reason) => write!(f, "Couldn't read the forecast: {}", reason),
+    }
+  }
+}
+
+impl std::error::Error for FetchError {}
+
+impl Data for FetchError {
+  fn same(&self, other: &Self) -> bool {
+    self == other
+  }
+}
+
+/// The one way the app talks HTTP, so that every request can be recorded or replayed.
+#[derive(Clone, Debug)]
+pub struct HttpClient {
+  client: reqwest::Client,
+  mode: HttpMode,
+}
+
+impl HttpClient {
+  pub fn new(mode: HttpMode) -> Self {
+    let client = reqwest::Client::builder()
+      .user_agent(USER_AGENT)
+      .build()
+      .unwrap_or_default();
+    Self { client, mode }
+  }
+
+  pub async fn get_json<T: DeserializeOwned>(
+    &self,
+    url: &str,
+    query: &[(&str, String)],
+  ) -> Result<T, FetchError> {
+    let body = match &self.mode {
+      HttpMode::Live => {
+        self
+          .fetch(url, query)
+          .await?
+      }
+      HttpMode::Record(dir) => {
+        let body = self
+          .fetch(url, query)
+          .await?;
+        record(dir, url, query, &body).map_err(|e| {
+          FetchError::Network(format!("couldn't record the response from {}: {}", url, e))
+        })?;
+        body
+      }
+      HttpMode::Replay(dir) => {
+        let path = fixture_path(dir, url, query).map_err(|e| FetchError::Network(e.to_string()))?;
+        let fallback =
+          fixture_path(dir, url, &[]).map_err(|e| FetchError::Network(e.to_string()))?;
+        fs::read_to_string(&path)
+          .or_else(|_| fs::read_to_string(&fallback))
+          .map_err(|e| {
+            FetchError::Network(format!("no recorded response at {}: {}", path.display(), e))
+          })?
+      }
+    };
+    serde_json::from_str(&body).map_err(|e| FetchError::Parse(e.to_string()))
+  }
+
+  async fn fetch(&self, url: &str, query: &[(&str, String)]) -> Result<String, FetchError> {
+    let response = self
+      .client
+      .get(url)
+      .query(query)
+      .header(ACCEPT, "application/geo+json, application/json")
+      .send()
+      .await
+      .map_err(|e| FetchError::Network(e.to_string()))?;
+    let status = response.status();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+      let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| {
+          value
+            .to_str()
+            .ok()
+        })
+        .and_then(|value| {
+          value
+            .parse()
+            .ok()
+        });
+      return Err(FetchError::RateLimited(retry_after));
+    }
+    if !status.is_success() {
+      return Err(FetchError::Status(status.as_u16()));
+    }
+    response
+      .text()
+      .await
+      .map_err(|e| FetchError::Network(e.to_string()))
+  }
+}
+
+impl Default for HttpClient {
+  fn default() -> Self {
+    Self::new(HttpMode::from_env())
+  }
+}
+
+fn record(dir: &Path, url: &str, query: &[(&str, String)], body: &str) -> anyhow::Result<()> {
+  let path = fixture_path(dir, url, query)?;
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  fs::write(&path, body)?;
+  Ok(())
+}
+
+// INFO: responses are keyed by host, path and a hash of the query string, e.g.
+// `api.open-meteo.com/v1/forecast-1b2c3d4e5f607182.json`, so a batched request doesn't overwrite
+// the single one to the same endpoint. Without a query it's just the path, which is also where
+// replay looks when nothing was recorded for the exact query, so hand-written fixtures needn't
+// spell out coordinates and variable lists.
+fn fixture_path(dir: &Path, url: &str, query: &[(&str, String)]) -> anyhow::Result<PathBuf> {
+  let mut url = Url::parse(url)?;
+  if !query.is_empty() {
+    url
+      .query_pairs_mut()
+      .extend_pairs(query);
+  }
+  let host = url
+    .host_str()
+    .unwrap_or("localhost");
+  let mut path = dir.join(host);
+  for segment in url
+    .path()
+    .split('/')
+    .filter(|segment| !segment.is_empty() && *segment != "..")
+  {
+    path.push(segment);
+  }
+  let mut path = path.into_os_string();
+  if let Some(query) = url.query() {
+    path.push(format!("-{:016x}", fnv1a(query)));
+  }
+  path.push(".json");
+  Ok(PathBuf::from(path))
+}
+
+// NOTE: not `DefaultHasher`, whose output may change between Rust releases and strand recordings
+fn fnv1a(text: &str) -> u64 {
+  text
+    .bytes()
+    .fold(0xcbf29ce484222325, |hash, byte| {
+      (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,16 @@
+pub mod api_models;
+pub mod app_data;
+pub mod dashboard;
+pub mod daylight_arc;
+pub mod db_models;
+pub mod hourly_chart;
+pub mod http;
+pub mod providers;
+pub mod queries;
+pub mod scheduler;
+pub mod screens;
+pub mod timezone;
+pub mod units;
+pub mod views;
+pub mod weather_condition;
+pub mod wind_compass;
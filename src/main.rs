@@ -1,39 +1,75 @@
 use std::str;
 
-use pollster::FutureExt as _;
 use vizia::prelude::*;
 
-mod api_models;
-mod app_data;
-mod db_models;
-mod queries;
-mod screens;
-mod views;
-
-use app_data::{rehydrate_from_db, AppData};
-use queries::setup_database;
-use screens::{new_location_form::*, today_view::*};
+use rain::app_data::{post_when_done, rehydrate_from_db, AppData, Screen};
+use rain::queries::Repository;
+use rain::scheduler::start_ticking;
+use rain::screens::{
+  daily_forecast::*, locations::*, new_location_form::*, settings::*, today_view::*,
+};
+use rain::views::{ErrorBanner, FreshnessBadge, NavBar};
 
 #[tokio::main]
 async fn main() -> Result<(), vizia::ApplicationError> {
   env_logger::init();
-  let _ = setup_database().await;
+  let repo = match Repository::open().await {
+    Ok(repo) => repo,
+    Err(e) => {
+      // keep going without history rather than refusing to show the weather
+      println!(
+        "failed to open the database, nothing will be saved: {:?}",
+        e
+      );
+      Repository::in_memory()
+        .await
+        .expect("Failed to open an in-memory database")
+    }
+  };
 
-  Application::new(|cx| {
-    let _ = rehydrate_from_db(cx).block_on();
+  Application::new(move |cx| {
+    post_when_done(cx.get_proxy(), rehydrate_from_db(repo.clone()));
 
     if let Ok(style) = str::from_utf8(include_bytes!("style.css")) {
       cx.add_stylesheet(style)
         .expect("Failed to add stylesheet");
     }
 
-    AppData::default().build(cx);
+    AppData::new(repo.clone()).build(cx);
+    start_ticking(cx);
+
+    NavBar::new(cx);
+    ErrorBanner::new(cx);
+    FreshnessBadge::new(cx);
 
-    Binding::new(cx, AppData::weather_data, |cx, lens| {
-      if let Some(forecast) = lens.get(cx) {
-        ForecastScreen::new(cx, forecast);
-      } else {
-        NewLocationForm::new(cx);
+    Binding::new(cx, AppData::screen, move |cx, screen| {
+      match screen.get(cx) {
+        Screen::Forecast => {
+          let repo = repo.clone();
+          Binding::new(cx, AppData::weather_data, move |cx, lens| {
+            if let Some(forecast) = lens.get(cx) {
+              ForecastScreen::new(cx, forecast);
+            } else {
+              NewLocationForm::new(cx, repo.clone());
+            }
+          });
+        }
+        Screen::Daily => {
+          let repo = repo.clone();
+          Binding::new(cx, AppData::weather_data, move |cx, lens| {
+            if let Some(forecast) = lens.get(cx) {
+              DailyForecastScreen::new(cx, forecast);
+            } else {
+              NewLocationForm::new(cx, repo.clone());
+            }
+          });
+        }
+        Screen::Locations => {
+          LocationsScreen::new(cx, repo.clone());
+        }
+        Screen::Settings => {
+          SettingsScreen::new(cx);
+        }
       }
     });
   })
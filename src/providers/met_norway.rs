@@ -0,0 +1,356 @@
+use chrono::{DateTime, FixedOffset, Offset, Utc};
+use serde::Deserialize;
+
+use crate::api_models::*;
+use crate::http::{FetchError, HttpClient};
+use crate::providers::{summarize_days, ForecastRequest, WeatherProvider, TIME_FORMAT};
+use crate::timezone::LocalZone;
+
+pub const BASE_URL: &str = "https://api.met.no/weatherapi/locationforecast/2.0/compact";
+
+pub struct MetNorway {
+  base_url: String,
+  http: HttpClient,
+}
+
+impl MetNorway {
+  pub fn new(base_url: impl Into<String>, http: HttpClient) -> Self {
+    Self {
+      base_url: base_url.into(),
+      http,
+    }
+  }
+}
+
+impl WeatherProvider for MetNorway {
+  async fn fetch(&self, request: &ForecastRequest) -> Result<Meteo, FetchError> {
+    // INFO: api.met.no asks for at most four decimals so responses can be cached
+    let query = [
+      ("lat", format!("{:.4}", request.lat)),
+      ("lon", format!("{:.4}", request.lng)),
+    ];
+    let response = self
+      .http
+      .get_json::<Response>(&self.base_url, &query)
+      .await?;
+    into_meteo(response, request)
+  }
+}
+
+#[derive(Deserialize, Debug)]
+struct Response {
+  geometry: Geometry,
+  properties: Properties,
+}
+
+#[derive(Deserialize, Debug)]
+struct Geometry {
+  // [lng, lat, altitude]
+  coordinates: Vec<f64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Properties {
+  timeseries: Vec<TimeStep>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TimeStep {
+  time: DateTime<Utc>,
+  data: StepData,
+}
+
+#[derive(Deserialize, Debug)]
+struct StepData {
+  instant: Instant,
+  next_1_hours: Option<Period>,
+  next_6_hours: Option<Period>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Instant {
+  details: InstantDetails,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct InstantDetails {
+  air_temperature: Option<f64>,
+  relative_humidity: Option<f64>,
+  dew_point_temperature: Option<f64>,
+  cloud_area_fraction: Option<f64>,
+  air_pressure_at_sea_level: Option<f64>,
+  wind_from_direction: Option<f64>,
+  wind_speed: Option<f64>,
+  wind_speed_of_gust: Option<f64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Period {
+  summary: Option<Summary>,
+  details: Option<PeriodDetails>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Summary {
+  symbol_code: String,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct PeriodDetails {
+  precipitation_amount: Option<f64>,
+  probability_of_precipitation: Option<f64>,
+}
+
+impl StepData {
+  /// The shortest period forecast past the first two days only comes in 6 hour steps.
+  fn period(&self) -> Option<&Period> {
+    self
+      .next_1_hours
+      .as_ref()
+      .or(
+        self
+          .next_6_hours
+          .as_ref(),
+      )
+  }
+
+  fn symbol_code(&self) -> Option<&str> {
+    self
+      .period()?
+      .summary
+      .as_ref()
+      .map(|summary| {
+        summary
+          .symbol_code
+          .as_str()
+      })
+  }
+
+  fn precipitation_amount(&self) -> Option<f64> {
+    self
+      .period()?
+      .details
+      .as_ref()?
+      .precipitation_amount
+  }
+
+  fn probability_of_precipitation(&self) -> Option<f64> {
+    self
+      .period()?
+      .details
+      .as_ref()?
+      .probability_of_precipitation
+  }
+}
+
+/// The timezone MET Norway's UTC timestamps are shown in. It doesn't tell us the local one, so
+/// it's the location's stored zone when there is one.
+fn local_zone(request: &ForecastRequest) -> LocalZone {
+  // NOTE: a guess from the longitude, an hour per 15°, until the location has a zone
+  let estimate =
+    FixedOffset::east_opt((request.lng / 15.0).round() as i32 * 3600).unwrap_or(Utc.fix());
+  LocalZone::parse(&request.timezone, estimate)
+}
+
+fn into_meteo(response: Response, request: &ForecastRequest) -> Result<Meteo, FetchError> {
+  let steps = response
+    .properties
+    .timeseries;
+  let first = steps
+    .first()
+    .ok_or_else(|| FetchError::Parse(String::from("MET Norway returned an empty timeseries")))?;
+  let coordinates = response
+    .geometry
+    .coordinates;
+  let zone = local_zone(request);
+
+  let details = &first
+    .data
+    .instant
+    .details;
+  let current = Current {
+    time: zone
+      .to_local(&first.time)
+      .format(TIME_FORMAT)
+      .to_string(),
+    interval: 3600.0,
+    temperature_2_m: details.air_temperature,
+    relative_humidity_2_m: details.relative_humidity,
+    is_day: first
+      .data
+      .symbol_code()
+      .map(is_day),
+    precipitation: first
+      .data
+      .precipitation_amount(),
+    weather_code: first
+      .data
+      .symbol_code()
+      .and_then(symbol_to_wmo_code),
+    cloud_cover: details.cloud_area_fraction,
+    pressure_msl: details.air_pressure_at_sea_level,
+    wind_speed_10_m: details.wind_speed,
+    wind_direction_10_m: details.wind_from_direction,
+    wind_gusts_10_m: details.wind_speed_of_gust,
+    ..Default::default()
+  };
+  let current_units = CurrentUnits {
+    time: String::from("iso8601"),
+    interval: String::from("seconds"),
+    temperature_2_m: String::from("°C"),
+    relative_humidity_2_m: String::from("%"),
+    precipitation: String::from("mm"),
+    weather_code: String::from("wmo code"),
+    cloud_cover: String::from("%"),
+    pressure_msl: String::from("hPa"),
+    wind_speed_10_m: String::from("m/s"),
+    wind_direction_10_m: String::from("°"),
+    wind_gusts_10_m: String::from("m/s"),
+    ..Default::default()
+  };
+
+  let mut hourly = Hourly::default();
+  for step in &steps {
+    let details = &step
+      .data
+      .instant
+      .details;
+    hourly
+      .time
+      .push(
+        zone
+          .to_local(&step.time)
+          .format(TIME_FORMAT)
+          .to_string(),
+      );
+    hourly
+      .temperature_2_m
+      .push(details.air_temperature);
+    hourly
+      .relative_humidity_2_m
+      .push(details.relative_humidity);
+    hourly
+      .dew_point_2_m
+      .push(details.dew_point_temperature);
+    hourly
+      .precipitation_probability
+      .push(
+        step
+          .data
+          .probability_of_precipitation(),
+      );
+    hourly
+      .precipitation
+      .push(
+        step
+          .data
+          .precipitation_amount(),
+      );
+    hourly
+      .weather_code
+      .push(
+        step
+          .data
+          .symbol_code()
+          .and_then(symbol_to_wmo_code)
+          .map(|code| code as f64),
+      );
+    hourly
+      .wind_speed_10_m
+      .push(details.wind_speed);
+    hourly
+      .wind_direction_10_m
+      .push(details.wind_from_direction);
+    hourly
+      .wind_gusts_10_m
+      .push(details.wind_speed_of_gust);
+  }
+  let hourly_units = HourlyUnits {
+    time: String::from("iso8601"),
+    temperature_2_m: String::from("°C"),
+    relative_humidity_2_m: String::from("%"),
+    dew_point_2_m: String::from("°C"),
+    precipitation_probability: String::from("%"),
+    precipitation: String::from("mm"),
+    weather_code: String::from("wmo code"),
+    wind_speed_10_m: String::from("m/s"),
+    wind_direction_10_m: String::from("°"),
+    wind_gusts_10_m: String::from("m/s"),
+    ..Default::default()
+  };
+
+  let (daily, daily_units) = summarize_days(&hourly, &hourly_units, request.forecast_days);
+  hourly.truncate(24);
+
+  let mut meteo = Meteo {
+    latitude: coordinates
+      .get(1)
+      .copied()
+      .unwrap_or(request.lat),
+    longitude: coordinates
+      .first()
+      .copied()
+      .unwrap_or(request.lng),
+    generationtime_ms: 0.0,
+    utc_offset_seconds: zone
+      .offset_at(&first.time)
+      .local_minus_utc() as f64,
+    timezone: zone.name(),
+    timezone_abbreviation: match zone {
+      LocalZone::Named(_) => zone.abbreviation(&first.time),
+      // solar time rather than the clocks on the wall there, so it says so
+      LocalZone::Fixed(_) => format!("{} (approximate)", zone.abbreviation(&first.time)),
+    },
+    elevation: coordinates
+      .get(2)
+      .copied()
+      .unwrap_or_default(),
+    current_units,
+    current,
+    hourly_units,
+    hourly,
+    daily_units,
+    daily,
+  };
+  meteo.convert_units(&request.units);
+  Ok(meteo)
+}
+
+fn is_day(symbol_code: &str) -> f64 {
+  if symbol_code.ends_with("_night") || symbol_code.ends_with("_polartwilight") {
+    0.0
+  } else {
+    1.0
+  }
+}
+
+// INFO: https://api.met.no/weatherapi/weathericon/2.0/documentation
+fn symbol_to_wmo_code(symbol_code: &str) -> Option<i64> {
+  let symbol = symbol_code
+    .split('_')
+    .next()?;
+  if symbol.contains("thunder") {
+    return Some(95);
+  }
+  match symbol {
+    "clearsky" => Some(0),
+    "fair" => Some(1),
+    "partlycloudy" => Some(2),
+    "cloudy" => Some(3),
+    "fog" => Some(45),
+    "lightrain" => Some(61),
+    "rain" => Some(63),
+    "heavyrain" => Some(65),
+    "lightsleet" => Some(66),
+    "sleet" | "heavysleet" => Some(67),
+    "lightsnow" => Some(71),
+    "snow" => Some(73),
+    "heavysnow" => Some(75),
+    "lightrainshowers" => Some(80),
+    "rainshowers" | "lightsleetshowers" | "sleetshowers" => Some(81),
+    "heavyrainshowers" | "heavysleetshowers" => Some(82),
+    "lightsnowshowers" | "snowshowers" => Some(85),
+    "heavysnowshowers" => Some(86),
+    _ => None,
+  }
+}
@@ -0,0 +1,248 @@
+use std::future::Future;
+
+use chrono::TimeDelta;
+use serde::{Deserialize, Serialize};
+use vizia::prelude::*;
+
+use crate::api_models::{Daily, DailyUnits, Hourly, HourlyUnits, Meteo};
+use crate::http::{FetchError, HttpClient};
+use crate::units::UnitPreferences;
+
+pub mod met_norway;
+pub mod nws;
+pub mod open_meteo;
+
+use met_norway::MetNorway;
+use nws::Nws;
+use open_meteo::OpenMeteo;
+
+/// Everything a provider needs to know to fetch a forecast for one place.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForecastRequest {
+  pub lat: f64,
+  pub lng: f64,
+  /// IANA name, or `auto` to let the provider work it out
+  pub timezone: String,
+  pub units: UnitPreferences,
+  pub forecast_days: u8,
+}
+
+/// A source of forecasts.
+///
+/// `Meteo` is the app's forecast model regardless of where the data came from: providers fill in
+/// what they have, leave the rest `None`, and hand back values already in `request.units`.
+pub trait WeatherProvider {
+  fn fetch(
+    &self,
+    request: &ForecastRequest,
+  ) -> impl Future<Output = Result<Meteo, FetchError>> + Send;
+}
+
+#[derive(Copy, Clone, Data, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ProviderKind {
+  #[default]
+  OpenMeteo,
+  MetNorway,
+  Nws,
+}
+
+impl ProviderKind {
+  pub const ALL: [ProviderKind; 3] = [
+    ProviderKind::OpenMeteo,
+    ProviderKind::MetNorway,
+    ProviderKind::Nws,
+  ];
+
+  /// The value stored in `Location.provider`.
+  pub fn id(&self) -> &'static str {
+    match self {
+      ProviderKind::OpenMeteo => "open_meteo",
+      ProviderKind::MetNorway => "met_norway",
+      ProviderKind::Nws => "nws",
+    }
+  }
+
+  pub fn from_id(id: &str) -> Option<Self> {
+    Self::ALL
+      .into_iter()
+      .find(|kind| kind.id() == id)
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      ProviderKind::OpenMeteo => "Open-Meteo",
+      ProviderKind::MetNorway => "MET Norway",
+      ProviderKind::Nws => "US NWS",
+    }
+  }
+
+  /// How often the provider publishes new data. Refetching sooner than this just returns the
+  /// same model run.
+  pub fn update_interval(&self) -> TimeDelta {
+    match self {
+      // most of the models behind Open-Meteo update hourly, some less often
+      ProviderKind::OpenMeteo => TimeDelta::hours(1),
+      ProviderKind::MetNorway => TimeDelta::minutes(30),
+      ProviderKind::Nws => TimeDelta::hours(1),
+    }
+  }
+
+  /// Whether the provider works out the location's IANA timezone itself, rather than falling
+  /// back on a guess worth keeping out of `Location.timezone`.
+  pub fn resolves_timezone(&self) -> bool {
+    match self {
+      ProviderKind::OpenMeteo => true,
+      ProviderKind::MetNorway => false,
+      ProviderKind::Nws => true,
+    }
+  }
+
+  pub async fn fetch(
+    &self,
+    http: &HttpClient,
+    request: &ForecastRequest,
+  ) -> Result<Meteo, FetchError> {
+    let http = http.clone();
+    match self {
+      ProviderKind::OpenMeteo => {
+        OpenMeteo::new(open_meteo::BASE_URL, http)
+          .fetch(request)
+          .await
+      }
+      ProviderKind::MetNorway => {
+        MetNorway::new(met_norway::BASE_URL, http)
+          .fetch(request)
+          .await
+      }
+      ProviderKind::Nws => {
+        Nws::new(nws::BASE_URL, http)
+          .fetch(request)
+          .await
+      }
+    }
+  }
+}
+
+/// Open-Meteo style timestamps, local to the forecast's timezone.
+pub const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+/// Builds daily summaries out of an hourly series, for providers that don't have their own.
+fn summarize_days(hourly: &Hourly, units: &HourlyUnits, days: u8) -> (Daily, DailyUnits) {
+  let mut daily = Daily::default();
+  for (index, time) in hourly
+    .time
+    .iter()
+    .enumerate()
+  {
+    let Some(date) = time.get(..10) else {
+      continue;
+    };
+    if daily
+      .time
+      .last()
+      .map(String::as_str)
+      != Some(date)
+    {
+      if daily
+        .time
+        .len()
+        == days as usize
+      {
+        break;
+      }
+      daily
+        .time
+        .push(String::from(date));
+      daily
+        .weather_code
+        .push(None);
+      daily
+        .temperature_2_m_max
+        .push(None);
+      daily
+        .temperature_2_m_min
+        .push(None);
+      daily
+        .daylight_duration
+        .push(None);
+      daily
+        .uv_index_max
+        .push(None);
+      daily
+        .precipitation_sum
+        .push(None);
+      daily
+        .rain_sum
+        .push(None);
+      daily
+        .snowfall_sum
+        .push(None);
+      daily
+        .precipitation_probability_max
+        .push(None);
+    }
+
+    let day = daily
+      .time
+      .len()
+      - 1;
+    let hour = |values: &[Option<f64>]| {
+      values
+        .get(index)
+        .copied()
+        .flatten()
+    };
+    fold(
+      &mut daily.weather_code[day],
+      hour(&hourly.weather_code),
+      f64::max,
+    );
+    fold(
+      &mut daily.temperature_2_m_max[day],
+      hour(&hourly.temperature_2_m),
+      f64::max,
+    );
+    fold(
+      &mut daily.temperature_2_m_min[day],
+      hour(&hourly.temperature_2_m),
+      f64::min,
+    );
+    fold(
+      &mut daily.precipitation_sum[day],
+      hour(&hourly.precipitation),
+      |sum, value| sum + value,
+    );
+    fold(
+      &mut daily.precipitation_probability_max[day],
+      hour(&hourly.precipitation_probability),
+      f64::max,
+    );
+  }
+
+  let daily_units = DailyUnits {
+    time: String::from("iso8601"),
+    weather_code: units
+      .weather_code
+      .clone(),
+    temperature_2_m_max: units
+      .temperature_2_m
+      .clone(),
+    temperature_2_m_min: units
+      .temperature_2_m
+      .clone(),
+    precipitation_sum: units
+      .precipitation
+      .clone(),
+    precipitation_probability_max: units
+      .precipitation_probability
+      .clone(),
+    ..Default::default()
+  };
+  (daily, daily_units)
+}
+
+fn fold(slot: &mut Option<f64>, value: Option<f64>, combine: fn(f64, f64) -> f64) {
+  if let Some(value) = value {
+    *slot = Some(slot.map_or(value, |current| combine(current, value)));
+  }
+}
@@ -0,0 +1,274 @@
+use chrono::{DateTime, FixedOffset};
+use serde::Deserialize;
+
+use crate::api_models::*;
+use crate::http::{FetchError, HttpClient};
+use crate::providers::{summarize_days, ForecastRequest, WeatherProvider, TIME_FORMAT};
+use crate::units::CARDINALS;
+
+pub const BASE_URL: &str = "https://api.weather.gov";
+
+/// The US National Weather Service gridpoint API. Only covers the US and its territories.
+pub struct Nws {
+  base_url: String,
+  http: HttpClient,
+}
+
+impl Nws {
+  pub fn new(base_url: impl Into<String>, http: HttpClient) -> Self {
+    Self {
+      base_url: base_url.into(),
+      http,
+    }
+  }
+
+  async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, FetchError> {
+    self
+      .http
+      .get_json::<T>(&format!("{}{}", self.base_url, path), &[])
+      .await
+  }
+}
+
+impl WeatherProvider for Nws {
+  async fn fetch(&self, request: &ForecastRequest) -> Result<Meteo, FetchError> {
+    // INFO: points resolve a coordinate to the forecast office grid that covers it
+    let point = self
+      .get::<PointResponse>(&format!("/points/{:.4},{:.4}", request.lat, request.lng))
+      .await?
+      .properties;
+    let forecast = self
+      .get::<ForecastResponse>(&format!(
+        "/gridpoints/{}/{},{}/forecast/hourly",
+        point.grid_id, point.grid_x, point.grid_y
+      ))
+      .await?
+      .properties;
+    into_meteo(point, forecast, request)
+  }
+}
+
+#[derive(Deserialize, Debug)]
+struct PointResponse {
+  properties: Point,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Point {
+  grid_id: String,
+  grid_x: i64,
+  grid_y: i64,
+  time_zone: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct ForecastResponse {
+  properties: Forecast,
+}
+
+#[derive(Deserialize, Debug)]
+struct Forecast {
+  periods: Vec<ForecastPeriod>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ForecastPeriod {
+  start_time: DateTime<FixedOffset>,
+  is_daytime: bool,
+  temperature: Option<f64>,
+  temperature_unit: String,
+  probability_of_precipitation: Option<QuantitativeValue>,
+  dewpoint: Option<QuantitativeValue>,
+  relative_humidity: Option<QuantitativeValue>,
+  wind_speed: String,
+  wind_direction: String,
+  short_forecast: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct QuantitativeValue {
+  value: Option<f64>,
+}
+
+fn value(quantity: &Option<QuantitativeValue>) -> Option<f64> {
+  quantity
+    .as_ref()?
+    .value
+}
+
+fn into_meteo(
+  point: Point,
+  forecast: Forecast,
+  request: &ForecastRequest,
+) -> Result<Meteo, FetchError> {
+  let periods = forecast.periods;
+  let first = periods
+    .first()
+    .ok_or_else(|| FetchError::Parse(String::from("NWS returned no forecast periods")))?;
+  let offset = first
+    .start_time
+    .offset();
+  let temperature_unit = match first
+    .temperature_unit
+    .as_str()
+  {
+    "C" => "°C",
+    _ => "°F",
+  };
+
+  let current = Current {
+    time: first
+      .start_time
+      .format(TIME_FORMAT)
+      .to_string(),
+    interval: 3600.0,
+    temperature_2_m: first.temperature,
+    relative_humidity_2_m: value(&first.relative_humidity),
+    is_day: Some(if first.is_daytime { 1.0 } else { 0.0 }),
+    weather_code: forecast_to_wmo_code(&first.short_forecast),
+    wind_speed_10_m: parse_wind_speed(&first.wind_speed),
+    wind_direction_10_m: cardinal_to_degrees(&first.wind_direction),
+    ..Default::default()
+  };
+  let current_units = CurrentUnits {
+    time: String::from("iso8601"),
+    interval: String::from("seconds"),
+    temperature_2_m: String::from(temperature_unit),
+    relative_humidity_2_m: String::from("%"),
+    weather_code: String::from("wmo code"),
+    wind_speed_10_m: String::from("mph"),
+    wind_direction_10_m: String::from("°"),
+    ..Default::default()
+  };
+
+  let mut hourly = Hourly::default();
+  for period in &periods {
+    hourly
+      .time
+      .push(
+        period
+          .start_time
+          .format(TIME_FORMAT)
+          .to_string(),
+      );
+    hourly
+      .temperature_2_m
+      .push(period.temperature);
+    hourly
+      .relative_humidity_2_m
+      .push(value(&period.relative_humidity));
+    hourly
+      .dew_point_2_m
+      .push(value(&period.dewpoint));
+    hourly
+      .precipitation_probability
+      .push(value(&period.probability_of_precipitation));
+    hourly
+      .weather_code
+      .push(forecast_to_wmo_code(&period.short_forecast).map(|code| code as f64));
+    hourly
+      .wind_speed_10_m
+      .push(parse_wind_speed(&period.wind_speed));
+    hourly
+      .wind_direction_10_m
+      .push(cardinal_to_degrees(&period.wind_direction));
+  }
+  let hourly_units = HourlyUnits {
+    time: String::from("iso8601"),
+    temperature_2_m: String::from(temperature_unit),
+    relative_humidity_2_m: String::from("%"),
+    // NOTE: the NWS reports dew point in °C even when temperature is in °F
+    dew_point_2_m: String::from("°C"),
+    precipitation_probability: String::from("%"),
+    weather_code: String::from("wmo code"),
+    wind_speed_10_m: String::from("mph"),
+    wind_direction_10_m: String::from("°"),
+    ..Default::default()
+  };
+
+  let (daily, daily_units) = summarize_days(&hourly, &hourly_units, request.forecast_days);
+  hourly.truncate(24);
+
+  let mut meteo = Meteo {
+    latitude: request.lat,
+    longitude: request.lng,
+    generationtime_ms: 0.0,
+    utc_offset_seconds: offset.local_minus_utc() as f64,
+    timezone: point.time_zone,
+    timezone_abbreviation: offset.to_string(),
+    elevation: 0.0,
+    current_units,
+    current,
+    hourly_units,
+    hourly,
+    daily_units,
+    daily,
+  };
+  meteo.convert_units(&request.units);
+  Ok(meteo)
+}
+
+// "10 mph" or "5 to 10 mph"; the upper end is the one worth showing
+fn parse_wind_speed(wind_speed: &str) -> Option<f64> {
+  wind_speed
+    .split_whitespace()
+    .filter_map(|word| {
+      word
+        .parse::<f64>()
+        .ok()
+    })
+    .last()
+}
+
+fn cardinal_to_degrees(cardinal: &str) -> Option<f64> {
+  CARDINALS
+    .iter()
+    .position(|c| *c == cardinal)
+    .map(|index| index as f64 * 22.5)
+}
+
+// INFO: short forecasts are free text like "Chance Showers And Thunderstorms", so this is a best guess
+fn forecast_to_wmo_code(short_forecast: &str) -> Option<i64> {
+  let forecast = short_forecast.to_lowercase();
+  let light = forecast.contains("light") || forecast.contains("slight");
+  let heavy = forecast.contains("heavy");
+  let intensity = |light_code, code, heavy_code| {
+    if light {
+      light_code
+    } else if heavy {
+      heavy_code
+    } else {
+      code
+    }
+  };
+  let code = if forecast.contains("thunder") {
+    95
+  } else if forecast.contains("freezing") {
+    intensity(66, 67, 67)
+  } else if forecast.contains("snow") && forecast.contains("shower") {
+    intensity(85, 85, 86)
+  } else if forecast.contains("snow") {
+    intensity(71, 73, 75)
+  } else if forecast.contains("shower") {
+    intensity(80, 81, 82)
+  } else if forecast.contains("rain") {
+    intensity(61, 63, 65)
+  } else if forecast.contains("drizzle") {
+    intensity(51, 53, 55)
+  } else if forecast.contains("fog") {
+    45
+  } else if forecast.contains("partly") {
+    2
+  } else if forecast.contains("mostly sunny") || forecast.contains("mostly clear") {
+    1
+  } else if forecast.contains("cloudy") {
+    3
+  } else if forecast.contains("sunny") || forecast.contains("clear") {
+    0
+  } else {
+    return None;
+  };
+  Some(code)
+}
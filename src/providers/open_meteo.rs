@@ -0,0 +1,143 @@
+use crate::api_models::*;
+use crate::http::{FetchError, HttpClient};
+use crate::providers::{ForecastRequest, WeatherProvider};
+
+pub const BASE_URL: &str = "https://api.open-meteo.com/v1/forecast";
+
+pub struct OpenMeteo {
+  base_url: String,
+  http: HttpClient,
+}
+
+impl OpenMeteo {
+  pub fn new(base_url: impl Into<String>, http: HttpClient) -> Self {
+    Self {
+      base_url: base_url.into(),
+      http,
+    }
+  }
+}
+
+pub fn query(request: &ForecastRequest) -> Vec<(&'static str, String)> {
+  let mut query = vec![
+    (
+      "latitude",
+      request
+        .lat
+        .to_string(),
+    ),
+    (
+      "longitude",
+      request
+        .lng
+        .to_string(),
+    ),
+    ("current", CURRENT_VARIABLES.join(",")),
+    ("hourly", HOURLY_VARIABLES.join(",")),
+    ("daily", DAILY_VARIABLES.join(",")),
+    (
+      "timezone",
+      request
+        .timezone
+        .clone(),
+    ),
+    (
+      "forecast_days",
+      request
+        .forecast_days
+        .to_string(),
+    ),
+    ("forecast_hours", String::from("24")),
+    ("past_hours", String::from("24")),
+    // yesterday's daily values, so today's can be compared with them
+    ("past_days", String::from("1")),
+  ];
+  query.extend(
+    request
+      .units
+      .query(),
+  );
+  query
+}
+
+/// The query for several places at once. Open-Meteo takes comma separated coordinates and
+/// timezones; everything else is shared, so it comes from the first request.
+pub fn batch_query(requests: &[ForecastRequest]) -> Vec<(&'static str, String)> {
+  let join = |field: fn(&ForecastRequest) -> String| {
+    requests
+      .iter()
+      .map(field)
+      .collect::<Vec<_>>()
+      .join(",")
+  };
+  let mut query = requests
+    .first()
+    .map(query)
+    .unwrap_or_default();
+  for (name, value) in query.iter_mut() {
+    match *name {
+      "latitude" => {
+        *value = join(|request| {
+          request
+            .lat
+            .to_string()
+        })
+      }
+      "longitude" => {
+        *value = join(|request| {
+          request
+            .lng
+            .to_string()
+        })
+      }
+      "timezone" => {
+        *value = join(|request| {
+          request
+            .timezone
+            .clone()
+        })
+      }
+      _ => {}
+    }
+  }
+  query
+}
+
+impl OpenMeteo {
+  /// Fetches forecasts for several places in one request, returned in the same order as
+  /// `requests`. They must all want the same units and number of days.
+  pub async fn fetch_many(&self, requests: &[ForecastRequest]) -> Result<Vec<Meteo>, FetchError> {
+    match requests {
+      [] => Ok(vec![]),
+      // a single place comes back as an object rather than a list of one
+      [request] => Ok(vec![
+        self
+          .fetch(request)
+          .await?,
+      ]),
+      _ => {
+        let forecasts = self
+          .http
+          .get_json::<Vec<Meteo>>(&self.base_url, &batch_query(requests))
+          .await?;
+        if forecasts.len() != requests.len() {
+          return Err(FetchError::Parse(format!(
+            "asked for {} forecasts, got {}",
+            requests.len(),
+            forecasts.len()
+          )));
+        }
+        Ok(forecasts)
+      }
+    }
+  }
+}
+
+impl WeatherProvider for OpenMeteo {
+  async fn fetch(&self, request: &ForecastRequest) -> Result<Meteo, FetchError> {
+    self
+      .http
+      .get_json::<Meteo>(&self.base_url, &query(request))
+      .await
+  }
+}
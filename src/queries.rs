@@ -1,10 +1,15 @@
-use chrono::Utc;
-use sqlx::{query_as, sqlite::SqlitePoolOptions};
-use std::fs;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::SqlitePool;
+use std::{fmt, fs};
 use xdg::BaseDirectories;
 
 use crate::api_models::*;
+use crate::dashboard::DashboardCell;
 use crate::db_models::*;
+use crate::providers::ProviderKind;
+use crate::scheduler::RefreshSchedule;
+use crate::units::UnitPreferences;
 
 fn get_state_home() -> anyhow::Result<std::path::PathBuf> {
   let bd = BaseDirectories::with_prefix("rain")?;
@@ -15,75 +20,192 @@ fn get_state_home() -> anyhow::Result<std::path::PathBuf> {
   Ok(state_home)
 }
 
-async fn get_database_connection(
-  state_home: std::path::PathBuf,
-) -> Option<sqlx::Pool<sqlx::Sqlite>> {
-  let path = state_home;
-  let db_url = format!("sqlite://{}rain.db?mode=rwc", path.to_str()?);
-
-  SqlitePoolOptions::new()
-    .max_connections(1)
-    .connect(&db_url)
-    .await
-    .ok()
+/// Owns the one connection pool the app uses. Cheap to clone; clones share the pool.
+#[derive(Clone)]
+pub struct Repository {
+  pool: SqlitePool,
 }
 
-pub async fn setup_database() -> anyhow::Result<()> {
-  let state_home = get_state_home()?;
-  if let Some(pool) = get_database_connection(state_home).await {
-    let _ = sqlx::migrate!()
-      .run(&pool)
-      .await?;
+impl fmt::Debug for Repository {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Repository")
+      .finish_non_exhaustive()
   }
-  Ok(())
 }
 
-pub async fn get_all_locations() -> anyhow::Result<Vec<Location>> {
-  let state_home = get_state_home()?;
-  if let Some(pool) = get_database_connection(state_home).await {
-    let all_locations = sqlx::query_as::<_, Location>("select * from Location;")
-      .fetch_all(&pool)
+impl Repository {
+  /// Opens (creating if needed) `rain.db` under the XDG state directory and migrates it.
+  pub async fn open() -> anyhow::Result<Self> {
+    let path = get_state_home()?.join("rain.db");
+    let options = SqliteConnectOptions::new()
+      .filename(path)
+      .create_if_missing(true);
+    let pool = SqlitePoolOptions::new()
+      .max_connections(1)
+      .connect_with(options)
+      .await?;
+    Self::migrated(pool).await
+  }
+
+  /// A private database that lives as long as the repository does. Nothing touches disk.
+  pub async fn in_memory() -> anyhow::Result<Self> {
+    // INFO: every connection to `:memory:` is its own database, so keep exactly one forever
+    let pool = SqlitePoolOptions::new()
+      .max_connections(1)
+      .min_connections(1)
+      .idle_timeout(None)
+      .max_lifetime(None)
+      .connect("sqlite::memory:")
       .await?;
+    Self::migrated(pool).await
+  }
+
+  async fn migrated(pool: SqlitePool) -> anyhow::Result<Self> {
+    sqlx::migrate!()
+      .run(&pool)
+      .await?;
+    Ok(Self { pool })
+  }
+
+  /// Every saved location, in the order the user arranged them.
+  pub async fn get_all_locations(&self) -> anyhow::Result<Vec<Location>> {
+    let all_locations = sqlx::query_as::<_, Location>(
+      "
+        select
+          *
+        from
+          Location
+        order by
+          position,
+          id;
+      ",
+    )
+    .fetch_all(&self.pool)
+    .await?;
     Ok(all_locations)
-  } else {
-    Err(anyhow::anyhow!("got no locations from db"))
   }
-  // Err(anyhow::anyhow!(
-  //   "get_all_locations currently de-implemented"
-  // ))
-}
 
-pub async fn get_latest_location() -> anyhow::Result<Location> {
-  let state_home = get_state_home()?;
-  if let Some(pool) = get_database_connection(state_home).await {
-    let latest_loc = query_as!(
-      Location,
+  /// The most recently added location.
+  pub async fn get_latest_location(&self) -> anyhow::Result<Location> {
+    let latest_loc = sqlx::query_as::<_, Location>(
       "
         select
           *
         from
           Location
+        order by
+          id desc
         limit
           1;
       ",
     )
-    .fetch_one(&pool)
+    .fetch_one(&self.pool)
     .await?;
-
     Ok(latest_loc)
-  } else {
-    Err(anyhow::anyhow!("could not get db connection"))
   }
-  // Err(anyhow::anyhow!(
-  //   "get_latest_location currently de-implemented"
-  // ))
-}
 
-pub async fn get_latest_historical_forecast(location: i64) -> anyhow::Result<HistoricalForecast> {
-  let state_home = get_state_home()?;
-  if let Some(pool) = get_database_connection(state_home).await {
-    let x = sqlx::query_as!(
-      HistoricalForecast,
+  /// The location the user last picked, or the first one in the list if that's gone.
+  pub async fn get_current_location(&self) -> anyhow::Result<Location> {
+    let current_id = self
+      .get_setting(CURRENT_LOCATION_KEY)
+      .await?
+      .and_then(|id| {
+        id.parse::<i64>()
+          .ok()
+      });
+    let current_loc = sqlx::query_as::<_, Location>(
+      "
+        select
+          *
+        from
+          Location
+        order by
+          id = ? desc,
+          position,
+          id
+        limit
+          1;
+      ",
+    )
+    .bind(current_id)
+    .fetch_one(&self.pool)
+    .await?;
+    Ok(current_loc)
+  }
+
+  pub async fn set_current_location(&self, location: i64) -> anyhow::Result<()> {
+    self
+      .set_setting(CURRENT_LOCATION_KEY, &location.to_string())
+      .await
+  }
+
+  pub async fn rename_location(&self, location: i64, name: &str) -> anyhow::Result<()> {
+    let _ = sqlx::query(
+      "
+        update Location
+        set
+          name = ?
+        where
+          id = ?;
+      ",
+    )
+    .bind(name)
+    .bind(location)
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  /// Deletes the location along with its forecast history.
+  pub async fn delete_location(&self, location: i64) -> anyhow::Result<()> {
+    let _ = sqlx::query(
+      "
+        delete from Location
+        where
+          id = ?;
+      ",
+    )
+    .bind(location)
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  /// Stores `locations` (ids) as the new list order.
+  pub async fn reorder_locations(&self, locations: &[i64]) -> anyhow::Result<()> {
+    let mut tx = self
+      .pool
+      .begin()
+      .await?;
+    for (position, location) in locations
+      .iter()
+      .enumerate()
+    {
+      let _ = sqlx::query(
+        "
+          update Location
+          set
+            position = ?
+          where
+            id = ?;
+        ",
+      )
+      .bind(position as i64)
+      .bind(location)
+      .execute(&mut *tx)
+      .await?;
+    }
+    tx.commit()
+      .await?;
+    Ok(())
+  }
+
+  /// The most recently fetched forecast for `location`.
+  pub async fn get_latest_historical_forecast(
+    &self,
+    location: i64,
+  ) -> anyhow::Result<HistoricalForecast> {
+    let x = sqlx::query_as::<_, HistoricalForecast>(
       "
         select
           *
@@ -91,59 +213,466 @@ pub async fn get_latest_historical_forecast(location: i64) -> anyhow::Result<His
           HistoricalForecast
         where
           location_id = ?
+        order by
+          timestamp desc,
+          id desc
         limit
           1
       ",
-      location
     )
-    .fetch_one(&pool)
+    .bind(location)
+    .fetch_one(&self.pool)
     .await?;
     Ok(x)
-  } else {
-    Err(anyhow::anyhow!("could not get db connection"))
   }
-  // Err(anyhow::anyhow!(
-  //   "get_latest_historical_forecast currently de-implemented"
-  // ))
-}
-
-pub async fn add_forecast_to_db(location: &Location, meteo: &Meteo) -> anyhow::Result<()> {
-  let forecast_json_string = serde_json::to_string(meteo)?;
-  let state_home = get_state_home()?;
 
-  if let Some(pool) = get_database_connection(state_home).await {
-    let _ = sqlx::query(
+  /// Stores the whole response, plus one row per hourly and daily value for range queries.
+  pub async fn add_forecast_to_db(
+    &self,
+    location: &Location,
+    meteo: &Meteo,
+    fetched_at: DateTime<Utc>,
+  ) -> anyhow::Result<()> {
+    let forecast_json_string = serde_json::to_string(meteo)?;
+    let fetched_at = format_timestamp(fetched_at);
+    let mut tx = self
+      .pool
+      .begin()
+      .await?;
+    let forecast_id = sqlx::query_scalar::<_, i64>(
       "
         insert into
           HistoricalForecast (location_id, response, timestamp)
         values
-          (?, ?, ?);
+          (?, ?, ?)
+        returning
+          id;
       ",
     )
     .bind(location.id)
     .bind(forecast_json_string)
-    .bind(Utc::now().to_string())
-    .execute(&pool)
+    .bind(&fetched_at)
+    .fetch_one(&mut *tx)
     .await?;
+
+    for (variable, values) in meteo
+      .hourly
+      .variables()
+    {
+      let unit = meteo
+        .hourly_units
+        .unit(variable)
+        .unwrap_or_default();
+      for (time, value) in meteo
+        .hourly
+        .time
+        .iter()
+        .zip(values)
+      {
+        let Some(valid_time) = meteo.parse_time(time) else {
+          continue;
+        };
+        let _ = sqlx::query(
+          "
+            insert
+            or ignore into HourlyValue (
+              forecast_id,
+              location_id,
+              fetched_at,
+              valid_time,
+              variable,
+              value,
+              unit
+            )
+            values
+              (?, ?, ?, ?, ?, ?, ?);
+          ",
+        )
+        .bind(forecast_id)
+        .bind(location.id)
+        .bind(&fetched_at)
+        .bind(format_timestamp(valid_time.with_timezone(&Utc)))
+        .bind(variable)
+        .bind(value)
+        .bind(unit)
+        .execute(&mut *tx)
+        .await?;
+      }
+    }
+
+    for (variable, values) in meteo
+      .daily
+      .variables()
+    {
+      let unit = meteo
+        .daily_units
+        .unit(variable)
+        .unwrap_or_default();
+      for (date, value) in meteo
+        .daily
+        .time
+        .iter()
+        .zip(values)
+      {
+        let _ = sqlx::query(
+          "
+            insert
+            or ignore into DailyValue (
+              forecast_id,
+              location_id,
+              fetched_at,
+              valid_date,
+              variable,
+              value,
+              unit
+            )
+            values
+              (?, ?, ?, ?, ?, ?, ?);
+          ",
+        )
+        .bind(forecast_id)
+        .bind(location.id)
+        .bind(&fetched_at)
+        .bind(date)
+        .bind(variable)
+        .bind(value)
+        .bind(unit)
+        .execute(&mut *tx)
+        .await?;
+      }
+    }
+
+    tx.commit()
+      .await?;
+    Ok(())
   }
-  Ok(())
-}
 
-pub async fn add_location_to_db(name: &str, geohash: &str) -> anyhow::Result<()> {
-  let state_home = get_state_home()?;
-  if let Some(pool) = get_database_connection(state_home).await {
-    let _ = sqlx::query(
+  /// Hourly values of `variable` valid in `[from, to)`, oldest first. Each hour comes from the
+  /// newest fetch that covered it.
+  pub async fn get_hourly_history(
+    &self,
+    location: i64,
+    variable: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+  ) -> anyhow::Result<Vec<HourlyValue>> {
+    let values = sqlx::query_as::<_, HourlyValue>(
+      "
+        select
+          *
+        from
+          HourlyValue hour
+        where
+          location_id = ?
+          and variable = ?
+          and valid_time >= ?
+          and valid_time < ?
+          and fetched_at = (
+            select
+              max(newest.fetched_at)
+            from
+              HourlyValue newest
+            where
+              newest.location_id = hour.location_id
+              and newest.variable = hour.variable
+              and newest.valid_time = hour.valid_time
+          )
+        group by
+          valid_time
+        order by
+          valid_time;
+      ",
+    )
+    .bind(location)
+    .bind(variable)
+    .bind(format_timestamp(from))
+    .bind(format_timestamp(to))
+    .fetch_all(&self.pool)
+    .await?;
+    Ok(values)
+  }
+
+  /// Daily values of `variable` for the local days `from..=to`, oldest first. Each day comes from
+  /// the newest fetch that covered it.
+  pub async fn get_daily_history(
+    &self,
+    location: i64,
+    variable: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+  ) -> anyhow::Result<Vec<DailyValue>> {
+    let values = sqlx::query_as::<_, DailyValue>(
+      "
+        select
+          *
+        from
+          DailyValue day
+        where
+          location_id = ?
+          and variable = ?
+          and valid_date between ? and ?
+          and fetched_at = (
+            select
+              max(newest.fetched_at)
+            from
+              DailyValue newest
+            where
+              newest.location_id = day.location_id
+              and newest.variable = day.variable
+              and newest.valid_date = day.valid_date
+          )
+        group by
+          valid_date
+        order by
+          valid_date;
+      ",
+    )
+    .bind(location)
+    .bind(variable)
+    .bind(from)
+    .bind(to)
+    .fetch_all(&self.pool)
+    .await?;
+    Ok(values)
+  }
+
+  /// Deletes the history `policy` doesn't keep, along with its hourly and daily values. "One a
+  /// day" is the newest fetch of each day on the location's own calendar. Returns how many
+  /// fetches were removed.
+  pub async fn prune(&self, policy: &RetentionPolicy, now: DateTime<Utc>) -> anyhow::Result<u64> {
+    let (keep_all, keep_daily) = policy.cutoffs(now);
+    let result = sqlx::query(
+      "
+        delete from HistoricalForecast
+        where
+          timestamp < ?
+          and (
+            timestamp < ?
+            or id not in (
+              select
+                id
+              from
+                (
+                  select
+                    id,
+                    row_number() over (
+                      partition by
+                        location_id,
+                        -- the day where the location is, by the offset the fetch was made under
+                        date(
+                          timestamp,
+                          coalesce(json_extract(response, '$.utc_offset_seconds'), 0) || ' seconds'
+                        )
+                      order by
+                        timestamp desc,
+                        id desc
+                    ) as newest_first
+                  from
+                    HistoricalForecast
+                )
+              where
+                newest_first = 1
+            )
+          );
+      ",
+    )
+    .bind(format_timestamp(keep_all))
+    .bind(keep_daily.map(format_timestamp))
+    .execute(&self.pool)
+    .await?;
+    Ok(result.rows_affected())
+  }
+
+  /// Gives the space freed by pruning back to the filesystem: folds the write-ahead log into the
+  /// database, then rebuilds it.
+  pub async fn compact(&self) -> anyhow::Result<()> {
+    sqlx::query("pragma wal_checkpoint(truncate);")
+      .execute(&self.pool)
+      .await?;
+    sqlx::query("vacuum;")
+      .execute(&self.pool)
+      .await?;
+    Ok(())
+  }
+
+  /// Saves a location, or updates the one already saved at `geohash`, and returns the stored row.
+  pub async fn save_location(
+    &self,
+    name: &str,
+    geohash: &str,
+    provider: ProviderKind,
+  ) -> anyhow::Result<Location> {
+    let location = sqlx::query_as::<_, Location>(
       "
         insert into
-          Location (geohash, name)
+          Location (geohash, name, provider, position)
         values
-          (?, ?);
-    ",
+          (
+            ?,
+            ?,
+            ?,
+            (
+              select
+                coalesce(max(position), -1) + 1
+              from
+                Location
+            )
+          )
+        on conflict (geohash) do update
+        set
+          name = excluded.name,
+          provider = excluded.provider
+        returning
+          *;
+      ",
     )
     .bind(geohash)
     .bind(name)
-    .execute(&pool)
+    .bind(provider.id())
+    .fetch_one(&self.pool)
     .await?;
+    Ok(location)
+  }
+
+  pub async fn get_setting(&self, key: &str) -> anyhow::Result<Option<String>> {
+    let value = sqlx::query_scalar::<_, String>(
+      "
+        select
+          value
+        from
+          Setting
+        where
+          key = ?;
+      ",
+    )
+    .bind(key)
+    .fetch_optional(&self.pool)
+    .await?;
+    Ok(value)
+  }
+
+  pub async fn set_setting(&self, key: &str, value: &str) -> anyhow::Result<()> {
+    let _ = sqlx::query(
+      "
+        insert into
+          Setting (key, value)
+        values
+          (?, ?)
+        on conflict (key) do update
+        set
+          value = excluded.value;
+      ",
+    )
+    .bind(key)
+    .bind(value)
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  pub async fn get_unit_preferences(&self) -> anyhow::Result<UnitPreferences> {
+    match self
+      .get_setting(UNIT_PREFERENCES_KEY)
+      .await?
+    {
+      Some(json) => Ok(serde_json::from_str(&json)?),
+      None => Ok(UnitPreferences::default()),
+    }
+  }
+
+  pub async fn save_unit_preferences(&self, prefs: &UnitPreferences) -> anyhow::Result<()> {
+    self
+      .set_setting(UNIT_PREFERENCES_KEY, &serde_json::to_string(prefs)?)
+      .await
+  }
+
+  pub async fn set_location_timezone(&self, geohash: &str, timezone: &str) -> anyhow::Result<()> {
+    let _ = sqlx::query(
+      "
+        update Location
+        set
+          timezone = ?
+        where
+          geohash = ?;
+      ",
+    )
+    .bind(timezone)
+    .bind(geohash)
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  pub async fn get_retention_policy(&self) -> anyhow::Result<RetentionPolicy> {
+    match self
+      .get_setting(RETENTION_POLICY_KEY)
+      .await?
+    {
+      Some(json) => Ok(serde_json::from_str(&json)?),
+      None => Ok(RetentionPolicy::default()),
+    }
+  }
+
+  pub async fn save_retention_policy(&self, policy: &RetentionPolicy) -> anyhow::Result<()> {
+    self
+      .set_setting(RETENTION_POLICY_KEY, &serde_json::to_string(policy)?)
+      .await
+  }
+
+  pub async fn get_refresh_schedule(&self) -> anyhow::Result<RefreshSchedule> {
+    match self
+      .get_setting(REFRESH_SCHEDULE_KEY)
+      .await?
+    {
+      Some(json) => Ok(serde_json::from_str(&json)?),
+      None => Ok(RefreshSchedule::default()),
+    }
+  }
+
+  pub async fn save_refresh_schedule(&self, schedule: &RefreshSchedule) -> anyhow::Result<()> {
+    self
+      .set_setting(REFRESH_SCHEDULE_KEY, &serde_json::to_string(schedule)?)
+      .await
+  }
+
+  pub async fn get_dashboard_cells(&self) -> anyhow::Result<Vec<DashboardCell>> {
+    match self
+      .get_setting(DASHBOARD_CELLS_KEY)
+      .await?
+    {
+      Some(json) => Ok(serde_json::from_str(&json)?),
+      None => Ok(DashboardCell::DEFAULT.to_vec()),
+    }
+  }
+
+  pub async fn save_dashboard_cells(&self, cells: &[DashboardCell]) -> anyhow::Result<()> {
+    self
+      .set_setting(DASHBOARD_CELLS_KEY, &serde_json::to_string(cells)?)
+      .await
+  }
+
+  pub async fn get_forecast_days(&self) -> anyhow::Result<u8> {
+    match self
+      .get_setting(FORECAST_DAYS_KEY)
+      .await?
+    {
+      Some(days) => Ok(days.parse()?),
+      None => Ok(DEFAULT_FORECAST_DAYS),
+    }
+  }
+
+  pub async fn save_forecast_days(&self, days: u8) -> anyhow::Result<()> {
+    self
+      .set_setting(FORECAST_DAYS_KEY, &days.to_string())
+      .await
   }
-  Ok(())
 }
+
+const UNIT_PREFERENCES_KEY: &str = "unit_preferences";
+const CURRENT_LOCATION_KEY: &str = "current_location";
+const RETENTION_POLICY_KEY: &str = "retention_policy";
+const REFRESH_SCHEDULE_KEY: &str = "refresh_schedule";
+const DASHBOARD_CELLS_KEY: &str = "dashboard_cells";
+
+const FORECAST_DAYS_KEY: &str = "forecast_days";
+pub const DEFAULT_FORECAST_DAYS: u8 = 7;
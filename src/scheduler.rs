@@ -0,0 +1,107 @@
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use vizia::prelude::*;
+
+use crate::app_data::AppEvent;
+
+/// How often the forecast refreshes itself while the app is open.
+#[derive(Copy, Clone, Data, Lens, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RefreshSchedule {
+  /// `None` turns automatic refreshes off.
+  pub interval_minutes: Option<u32>,
+  /// Refresh every saved location, not just the one on screen.
+  pub all_locations: bool,
+}
+
+impl Default for RefreshSchedule {
+  fn default() -> Self {
+    Self {
+      interval_minutes: Some(30),
+      all_locations: false,
+    }
+  }
+}
+
+impl RefreshSchedule {
+  pub const INTERVAL_CHOICES: [Option<u32>; 5] = [None, Some(15), Some(30), Some(60), Some(180)];
+
+  pub fn interval(&self) -> Option<TimeDelta> {
+    self
+      .interval_minutes
+      .map(|minutes| TimeDelta::minutes(minutes as i64))
+  }
+}
+
+/// Decides when the next automatic refresh is due.
+///
+/// It's driven by wall-clock ticks rather than a sleeping timer, so a machine that was suspended
+/// through a refresh notices as soon as it wakes up instead of waiting out the rest of a sleep.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Scheduler {
+  last_tick: Option<DateTime<Utc>>,
+  last_attempt: Option<DateTime<Utc>>,
+  failures: u32,
+}
+
+impl Scheduler {
+  /// How often the clock is checked.
+  pub const TICK: Duration = Duration::from_secs(60);
+  /// Failures double the wait, up to this long.
+  pub const MAX_BACKOFF: TimeDelta = TimeDelta::hours(6);
+
+  /// Records a tick at `now` and says whether a refresh should start. It only counts towards
+  /// the interval once `attempted` says one did.
+  pub fn tick(&mut self, schedule: &RefreshSchedule, now: DateTime<Utc>) -> bool {
+    // a gap much longer than a tick means the process was asleep
+    let woke = self
+      .last_tick
+      .is_some_and(|last| now - last > TimeDelta::from_std(Self::TICK * 3).unwrap_or_default());
+    self.last_tick = Some(now);
+    let Some(wait) = self.wait(schedule) else {
+      return false;
+    };
+    woke
+      || self
+        .last_attempt
+        .map_or(true, |last| now - last >= wait)
+  }
+
+  /// Starts the wait for the next refresh from `now`, when a due tick sent off a fetch.
+  pub fn attempted(&mut self, now: DateTime<Utc>) {
+    self.last_attempt = Some(now);
+  }
+
+  /// How long to wait after the last attempt, taking failures into account.
+  pub fn wait(&self, schedule: &RefreshSchedule) -> Option<TimeDelta> {
+    let interval = schedule.interval()?;
+    let backoff = interval
+      * 2_i32.saturating_pow(
+        self
+          .failures
+          .min(16),
+      );
+    Some(backoff.min(Self::MAX_BACKOFF.max(interval)))
+  }
+
+  pub fn succeeded(&mut self) {
+    self.failures = 0;
+  }
+
+  pub fn failed(&mut self) {
+    self.failures += 1;
+  }
+}
+
+/// Posts `AppEvent::Tick` every `Scheduler::TICK` for as long as the app runs.
+pub fn start_ticking(cx: &mut Context) {
+  cx.spawn(|proxy| loop {
+    thread::sleep(Scheduler::TICK);
+    if let Err(e) = proxy.emit(AppEvent::Tick(Utc::now())) {
+      println!("failed to post scheduler tick: {:?}", e);
+      return;
+    }
+  });
+}
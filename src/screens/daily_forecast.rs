@@ -0,0 +1,44 @@
+use vizia::prelude::*;
+
+use super::api_models::Meteo;
+use super::app_data::AppData;
+use super::views::DayCard;
+
+pub struct DailyForecastScreen {
+  forecast: Meteo,
+}
+
+impl DailyForecastScreen {
+  pub fn new(cx: &mut Context, forecast: Meteo) -> Handle<Self> {
+    Self {
+      forecast: forecast.clone(),
+    }
+    .build(cx, |cx| {
+      ScrollView::new(cx, |cx| {
+        VStack::new(cx, |cx| {
+          // the daily series starts a day back for comparison, and days drop off as they end
+          let forecast = forecast.clone();
+          Binding::new(cx, AppData::now, move |cx, now| {
+            let first = forecast.index_of_now(
+              &forecast
+                .daily
+                .time,
+              now.get(cx),
+            );
+            for day in first
+              ..forecast
+                .daily
+                .time
+                .len()
+            {
+              DayCard::new(cx, &forecast.daily, &forecast.daily_units, day);
+            }
+          });
+        })
+        .class("col");
+      });
+    })
+  }
+}
+
+impl View for DailyForecastScreen {}
@@ -0,0 +1,212 @@
+use vizia::prelude::*;
+
+use super::app_data::{AppData, AppEvent};
+use super::db_models::Location;
+use super::new_location_form::NewLocationForm;
+use super::queries::Repository;
+
+enum LocationsEvent {
+  StartRename(i64, String),
+  SetDraftName(String),
+  SubmitRename,
+  CancelRename,
+  DragStart(usize),
+  DragOver(usize),
+  /// The pointer left the list mid-drag, so the drag is off.
+  CancelDrag,
+}
+
+#[derive(Default, Debug, Clone, Lens)]
+struct LocationsState {
+  pub renaming: Option<i64>,
+  pub draft_name: String,
+  pub dragging: Option<usize>,
+  pub drop_target: Option<usize>,
+}
+
+impl Model for LocationsState {
+  fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+    event.map(|locations_event, _meta| match locations_event {
+      LocationsEvent::StartRename(id, name) => {
+        println!("LocationsEvent::StartRename({}, {:?})", id, name);
+        self.renaming = Some(*id);
+        self.draft_name = name.to_string();
+      }
+
+      LocationsEvent::SetDraftName(name) => {
+        self.draft_name = name.to_string();
+      }
+
+      LocationsEvent::SubmitRename => {
+        println!("LocationsEvent::SubmitRename");
+        let name = self
+          .draft_name
+          .trim()
+          .to_string();
+        if let Some(id) = self
+          .renaming
+          .take()
+        {
+          if !name.is_empty() {
+            cx.emit(AppEvent::RenameLocation(id, name));
+          }
+        }
+      }
+
+      LocationsEvent::CancelRename => {
+        println!("LocationsEvent::CancelRename");
+        self.renaming = None;
+      }
+
+      LocationsEvent::DragStart(index) => {
+        println!("LocationsEvent::DragStart({})", index);
+        self.dragging = Some(*index);
+        self.drop_target = Some(*index);
+      }
+
+      LocationsEvent::DragOver(index) => {
+        if self
+          .dragging
+          .is_some()
+        {
+          self.drop_target = Some(*index);
+        }
+      }
+
+      LocationsEvent::CancelDrag => {
+        if self
+          .dragging
+          .is_some()
+        {
+          println!("LocationsEvent::CancelDrag");
+          self.dragging = None;
+          self.drop_target = None;
+        }
+      }
+    });
+
+    // INFO: a drag ends wherever the button comes up, dropping onto the last row it was over
+    event.map(|window_event, _meta| {
+      if let WindowEvent::MouseUp(_) = window_event {
+        self.drop_dragged(cx);
+      }
+    });
+  }
+}
+
+impl LocationsState {
+  fn drop_dragged(&mut self, cx: &mut EventContext) {
+    if let (Some(from), Some(to)) = (
+      self
+        .dragging
+        .take(),
+      self
+        .drop_target
+        .take(),
+    ) {
+      println!("LocationsState: dropping {} onto {}", from, to);
+      if from != to {
+        cx.emit(AppEvent::MoveLocation(from, to));
+      }
+    }
+  }
+}
+
+pub struct LocationsScreen {}
+
+impl LocationsScreen {
+  pub fn new(cx: &mut Context, repo: Repository) -> Handle<Self> {
+    Self {}.build(cx, |cx| {
+      LocationsState::default().build(cx);
+      ScrollView::new(cx, |cx| {
+        VStack::new(cx, |cx| {
+          HStack::new(cx, |cx| {
+            Label::new(cx, "Locations").class("title");
+            Button::new(cx, |cx| Label::new(cx, "Refresh all")).on_press(|ex| {
+              ex.emit(AppEvent::ForceRefresh);
+              ex.emit(AppEvent::RefreshAllLocations);
+            });
+          })
+          .class("row");
+          VStack::new(cx, |cx| {
+            Binding::new(cx, AppData::locations, |cx, lens| {
+              let locations = lens.get(cx);
+              let count = locations.len();
+              for (index, location) in locations
+                .into_iter()
+                .enumerate()
+              {
+                location_row(cx, index, count, location);
+              }
+            });
+          })
+          .class("location_list")
+          .on_hover_out(|ex| ex.emit(LocationsEvent::CancelDrag));
+
+          Label::new(cx, "Add a location").class("title");
+          NewLocationForm::new(cx, repo);
+        })
+        .class("col");
+      });
+    })
+  }
+}
+
+impl View for LocationsScreen {}
+
+fn location_row(cx: &mut Context, index: usize, count: usize, location: Location) {
+  let id = location.id;
+  let provider = location.provider();
+  HStack::new(cx, |cx| {
+    // INFO: grab the handle to drag the row, let go over another row to drop it there
+    Label::new(cx, "≡")
+      .class("drag_handle")
+      .on_press_down(move |ex| ex.emit(LocationsEvent::DragStart(index)));
+
+    Binding::new(cx, LocationsState::renaming, move |cx, lens| {
+      if lens.get(cx) == Some(id) {
+        Textbox::new(cx, LocationsState::draft_name)
+          .on_edit(|ex, name| ex.emit(LocationsEvent::SetDraftName(name)))
+          .class("form_input");
+        Button::new(cx, |cx| Label::new(cx, "Save"))
+          .on_press(|ex| ex.emit(LocationsEvent::SubmitRename));
+        Button::new(cx, |cx| Label::new(cx, "Cancel"))
+          .on_press(|ex| ex.emit(LocationsEvent::CancelRename));
+      } else {
+        let name = location
+          .name
+          .clone();
+        Binding::new(cx, AppData::saved_location, move |cx, lens| {
+          let current = lens
+            .get(cx)
+            .map(|location| location.id)
+            == Some(id);
+          Button::new(cx, |cx| Label::new(cx, &name))
+            .on_press(move |ex| ex.emit(AppEvent::SelectLocation(id)))
+            .toggle_class("selected", current);
+        });
+        let name = location
+          .name
+          .clone();
+        Button::new(cx, |cx| Label::new(cx, "Rename"))
+          .on_press(move |ex| ex.emit(LocationsEvent::StartRename(id, name.clone())));
+      }
+    });
+
+    Label::new(cx, provider.name());
+    Button::new(cx, |cx| Label::new(cx, "↑"))
+      .on_press(move |ex| ex.emit(AppEvent::MoveLocation(index, index.saturating_sub(1))))
+      .disabled(index == 0);
+    Button::new(cx, |cx| Label::new(cx, "↓"))
+      .on_press(move |ex| ex.emit(AppEvent::MoveLocation(index, index + 1)))
+      .disabled(index + 1 >= count);
+    Button::new(cx, |cx| Label::new(cx, "Delete"))
+      .on_press(move |ex| ex.emit(AppEvent::DeleteLocation(id)));
+  })
+  .class("row")
+  .toggle_class(
+    "drop_target",
+    LocationsState::drop_target.map(move |target| *target == Some(index)),
+  )
+  .on_hover(move |ex| ex.emit(LocationsEvent::DragOver(index)));
+}
@@ -1,4 +1,10 @@
+pub mod daily_forecast;
+pub mod locations;
 pub mod new_location_form;
+pub mod settings;
 pub mod today_view;
 
-use super::{api_models, app_data, queries, views};
+use super::{
+  api_models, app_data, dashboard, daylight_arc, db_models, hourly_chart, providers, queries,
+  scheduler, units, views, weather_condition, wind_compass,
+};
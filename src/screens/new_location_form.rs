@@ -1,15 +1,21 @@
 use anyhow::Error;
-use pollster::FutureExt as _;
 use validator::{Validate, ValidationError};
 use validator_struct::ValidatorStruct;
 use vizia::prelude::*;
 
-use super::{app_data::AppEvent, queries::add_location_to_db};
+use super::{
+  app_data::{run_in_background, AppEvent},
+  db_models::Location,
+  providers::ProviderKind,
+  queries::Repository,
+};
 
 enum FormEvent {
   SetName(String),
   SetGeohash(String),
+  SetProvider(ProviderKind),
   Submit,
+  Submitted(Location),
   SubmitError(Error),
   Validate,
 }
@@ -21,9 +27,12 @@ struct FormState {
   pub geohash: String,
   #[validate(length(min = 1))]
   pub name: String,
+  pub provider: ProviderKind,
   pub submitting: bool,
   pub validation_errors: Option<FormStateError>,
   pub error_message: Option<String>,
+  #[lens(ignore)]
+  pub repo: Option<Repository>,
 }
 
 impl vizia::binding::Data for FormStateError {
@@ -60,6 +69,12 @@ impl Model for FormState {
           cx.emit(FormEvent::Validate);
         }
 
+        FormEvent::SetProvider(provider) => {
+          println!("FormEvent::SetProvider({:?})", provider);
+          self.provider = *provider;
+          println!("New State: {:#?}", self);
+        }
+
         FormEvent::Submit => {
           println!("FormEvent::Submit");
           self.submitting = true;
@@ -67,25 +82,46 @@ impl Model for FormState {
             .validation_errors
             .is_none()
           {
-            match add_location_to_db(&self.name, &self.geohash).block_on() {
-              Ok(()) => cx.emit(AppEvent::ConfirmLocation(
-                self
-                  .name
-                  .clone(),
-                self
-                  .geohash
-                  .clone(),
-              )),
-              Err(e) => {
-                cx.emit(FormEvent::SubmitError(e));
+            let (name, geohash, provider) = (
+              self
+                .name
+                .clone(),
+              self
+                .geohash
+                .clone(),
+              self.provider,
+            );
+            let repo = self
+              .repo
+              .clone();
+            run_in_background(cx, async move {
+              let Some(repo) = repo else {
+                return vec![FormEvent::SubmitError(anyhow::anyhow!(
+                  "no database to save to"
+                ))];
+              };
+              match repo
+                .save_location(&name, &geohash, provider)
+                .await
+              {
+                Ok(location) => vec![FormEvent::Submitted(location)],
+                Err(e) => vec![FormEvent::SubmitError(e)],
               }
-            };
+            });
+          } else {
+            self.submitting = false;
           }
+        }
+
+        FormEvent::Submitted(location) => {
+          println!("FormEvent::Submitted({:#?})", location);
           self.submitting = false;
+          cx.emit(AppEvent::ConfirmLocation(location.clone()));
         }
 
         FormEvent::SubmitError(e) => {
           println!("FormEvent::DisplayError");
+          self.submitting = false;
           self.error_message = Some(e.to_string());
           println!("New State: {:#?}", self);
         }
@@ -105,9 +141,13 @@ impl Model for FormState {
 pub struct NewLocationForm {}
 
 impl NewLocationForm {
-  pub fn new(cx: &mut Context) -> Handle<Self> {
+  pub fn new(cx: &mut Context, repo: Repository) -> Handle<Self> {
     Self {}.build(cx, |cx| {
-      FormState::default().build(cx);
+      FormState {
+        repo: Some(repo),
+        ..Default::default()
+      }
+      .build(cx);
       Binding::new(cx, FormState::error_message, |cx, lens| {
         if let Some(error_message) = lens.get(cx) {
           Label::new(cx, format!("Error: {}", error_message)).class("error");
@@ -128,6 +168,20 @@ impl NewLocationForm {
         })
         .class("row");
 
+        Binding::new(cx, FormState::provider, |cx, lens| {
+          let selected = lens.get(cx);
+          HStack::new(cx, |cx| {
+            for provider in ProviderKind::ALL {
+              Button::new(cx, |cx| Label::new(cx, provider.name()))
+                .on_press(move |ex| {
+                  ex.emit(FormEvent::SetProvider(provider));
+                })
+                .toggle_class("selected", selected == provider);
+            }
+          })
+          .class("row");
+        });
+
         Binding::new(cx, FormState::validation_errors, |cx, lens| {
           let errors = lens.get(cx);
           Label::new(cx, format!("Errors: {:#?}", errors));
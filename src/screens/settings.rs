@@ -0,0 +1,281 @@
+use vizia::prelude::*;
+
+use super::app_data::{AppData, AppEvent, MAX_FORECAST_DAYS};
+use super::dashboard::DashboardCell;
+use super::db_models::RetentionPolicy;
+use super::scheduler::RefreshSchedule;
+use super::units::*;
+
+pub struct SettingsScreen {}
+
+impl SettingsScreen {
+  pub fn new(cx: &mut Context) -> Handle<Self> {
+    Self {}.build(cx, |cx| {
+      Binding::new(cx, AppData::units, |cx, lens| {
+        let prefs = lens.get(cx);
+        VStack::new(cx, |cx| {
+          Label::new(cx, "Units").class("title");
+
+          HStack::new(cx, |cx| {
+            for system in UnitSystem::ALL {
+              Button::new(cx, |cx| Label::new(cx, system.name()))
+                .on_press(move |ex| {
+                  ex.emit(AppEvent::ChangeUnits(UnitPreferences::preset(system)));
+                })
+                .toggle_class("selected", prefs.system == system);
+            }
+          })
+          .class("row");
+
+          HStack::new(cx, |cx| {
+            Label::new(cx, "Temperature");
+            override_button(
+              cx,
+              "Preset",
+              prefs
+                .temperature_override
+                .is_none(),
+              UnitPreferences {
+                temperature_override: None,
+                ..prefs
+              },
+            );
+            for unit in TemperatureUnit::ALL {
+              override_button(
+                cx,
+                unit.label(),
+                prefs.temperature_override == Some(unit),
+                UnitPreferences {
+                  temperature_override: Some(unit),
+                  ..prefs
+                },
+              );
+            }
+          })
+          .class("row");
+
+          HStack::new(cx, |cx| {
+            Label::new(cx, "Wind speed");
+            override_button(
+              cx,
+              "Preset",
+              prefs
+                .wind_speed_override
+                .is_none(),
+              UnitPreferences {
+                wind_speed_override: None,
+                ..prefs
+              },
+            );
+            for unit in WindSpeedUnit::ALL {
+              override_button(
+                cx,
+                unit.label(),
+                prefs.wind_speed_override == Some(unit),
+                UnitPreferences {
+                  wind_speed_override: Some(unit),
+                  ..prefs
+                },
+              );
+            }
+          })
+          .class("row");
+
+          HStack::new(cx, |cx| {
+            Label::new(cx, "Precipitation");
+            override_button(
+              cx,
+              "Preset",
+              prefs
+                .precipitation_override
+                .is_none(),
+              UnitPreferences {
+                precipitation_override: None,
+                ..prefs
+              },
+            );
+            for unit in PrecipitationUnit::ALL {
+              override_button(
+                cx,
+                unit.label(),
+                prefs.precipitation_override == Some(unit),
+                UnitPreferences {
+                  precipitation_override: Some(unit),
+                  ..prefs
+                },
+              );
+            }
+          })
+          .class("row");
+        })
+        .class("col");
+      });
+
+      Binding::new(cx, AppData::forecast_days, |cx, lens| {
+        let days = lens.get(cx);
+        HStack::new(cx, |cx| {
+          Label::new(cx, "Forecast days");
+          Button::new(cx, |cx| Label::new(cx, "-"))
+            .on_press(move |ex| {
+              ex.emit(AppEvent::ChangeForecastDays(days.saturating_sub(1)));
+            })
+            .disabled(days <= 1);
+          Label::new(cx, days.to_string());
+          Button::new(cx, |cx| Label::new(cx, "+"))
+            .on_press(move |ex| {
+              ex.emit(AppEvent::ChangeForecastDays(days + 1));
+            })
+            .disabled(days >= MAX_FORECAST_DAYS);
+        })
+        .class("row");
+      });
+
+      Binding::new(cx, AppData::dashboard_cells, |cx, lens| {
+        let cells = lens.get(cx);
+        VStack::new(cx, |cx| {
+          Label::new(cx, "Dashboard").class("title");
+          for row in DashboardCell::ALL.chunks(5) {
+            HStack::new(cx, |cx| {
+              for &cell in row {
+                let shown = cells.contains(&cell);
+                // keep the dashboard in a fixed order however the cells were picked
+                let toggled: Vec<_> = DashboardCell::ALL
+                  .into_iter()
+                  .filter(|other| {
+                    if *other == cell {
+                      !shown
+                    } else {
+                      cells.contains(other)
+                    }
+                  })
+                  .collect();
+                Button::new(cx, |cx| Label::new(cx, cell.label()))
+                  .on_press(move |ex| ex.emit(AppEvent::ChangeDashboardCells(toggled.clone())))
+                  .toggle_class("selected", shown);
+              }
+            })
+            .class("row");
+          }
+        })
+        .class("col");
+      });
+
+      Binding::new(cx, AppData::retention, |cx, lens| {
+        let policy = lens.get(cx);
+        VStack::new(cx, |cx| {
+          Label::new(cx, "History").class("title");
+
+          HStack::new(cx, |cx| {
+            Label::new(cx, "Keep every fetch for");
+            Button::new(cx, |cx| Label::new(cx, "-"))
+              .on_press(move |ex| {
+                ex.emit(AppEvent::ChangeRetention(RetentionPolicy {
+                  keep_all_days: policy
+                    .keep_all_days
+                    .saturating_sub(1),
+                  ..policy
+                }));
+              })
+              .disabled(policy.keep_all_days == 0);
+            Label::new(cx, format!("{} days", policy.keep_all_days));
+            Button::new(cx, |cx| Label::new(cx, "+")).on_press(move |ex| {
+              ex.emit(AppEvent::ChangeRetention(RetentionPolicy {
+                keep_all_days: policy.keep_all_days + 1,
+                ..policy
+              }));
+            });
+          })
+          .class("row");
+
+          HStack::new(cx, |cx| {
+            Label::new(cx, "Then one a day for");
+            for choice in RetentionPolicy::DAILY_CHOICES {
+              let text = match choice {
+                Some(days) => format!("{} days", days),
+                None => String::from("Forever"),
+              };
+              Button::new(cx, |cx| Label::new(cx, text))
+                .on_press(move |ex| {
+                  ex.emit(AppEvent::ChangeRetention(RetentionPolicy {
+                    keep_daily_days: choice,
+                    ..policy
+                  }));
+                })
+                .toggle_class("selected", policy.keep_daily_days == choice);
+            }
+          })
+          .class("row");
+        })
+        .class("col");
+      });
+
+      Binding::new(cx, AppData::refresh_schedule, |cx, lens| {
+        let schedule = lens.get(cx);
+        VStack::new(cx, |cx| {
+          Label::new(cx, "Automatic refresh").class("title");
+
+          HStack::new(cx, |cx| {
+            for choice in RefreshSchedule::INTERVAL_CHOICES {
+              let text = match choice {
+                Some(minutes) if minutes % 60 == 0 => format!("{} h", minutes / 60),
+                Some(minutes) => format!("{} min", minutes),
+                None => String::from("Off"),
+              };
+              Button::new(cx, |cx| Label::new(cx, text))
+                .on_press(move |ex| {
+                  ex.emit(AppEvent::ChangeRefreshSchedule(RefreshSchedule {
+                    interval_minutes: choice,
+                    ..schedule
+                  }));
+                })
+                .toggle_class("selected", schedule.interval_minutes == choice);
+            }
+          })
+          .class("row");
+
+          HStack::new(cx, |cx| {
+            Button::new(cx, |cx| Label::new(cx, "Current location"))
+              .on_press(move |ex| {
+                ex.emit(AppEvent::ChangeRefreshSchedule(RefreshSchedule {
+                  all_locations: false,
+                  ..schedule
+                }));
+              })
+              .toggle_class("selected", !schedule.all_locations);
+            Button::new(cx, |cx| Label::new(cx, "All locations"))
+              .on_press(move |ex| {
+                ex.emit(AppEvent::ChangeRefreshSchedule(RefreshSchedule {
+                  all_locations: true,
+                  ..schedule
+                }));
+              })
+              .toggle_class("selected", schedule.all_locations);
+          })
+          .class("row");
+        })
+        .class("col");
+      });
+
+      HStack::new(cx, |cx| {
+        Button::new(cx, |cx| Label::new(cx, "Compact database"))
+          .on_press(|ex| ex.emit(AppEvent::CompactDatabase));
+        Binding::new(cx, AppData::maintenance_status, |cx, lens| {
+          if let Some(status) = lens.get(cx) {
+            Label::new(cx, status);
+          }
+        });
+      })
+      .class("row");
+    })
+  }
+}
+
+impl View for SettingsScreen {}
+
+fn override_button(cx: &mut Context, text: &str, selected: bool, prefs: UnitPreferences) {
+  Button::new(cx, |cx| Label::new(cx, text))
+    .on_press(move |ex| {
+      ex.emit(AppEvent::ChangeUnits(prefs));
+    })
+    .toggle_class("selected", selected);
+}
@@ -1,7 +1,13 @@
 use vizia::prelude::*;
 
 use super::api_models::Meteo;
-use super::views::lookup_weather_text;
+use super::app_data::AppData;
+use super::dashboard::Dashboard;
+use super::daylight_arc::DaylightArc;
+use super::hourly_chart::HourlyChart;
+use super::views::{HourCard, WeatherCode};
+use super::weather_condition::WeatherCondition;
+use super::wind_compass::WindCompass;
 
 pub struct ForecastScreen {
   forecast: Meteo,
@@ -14,17 +20,70 @@ impl ForecastScreen {
     }
     .build(cx, |cx| {
       Label::new(cx, "Right now:").class("title");
-      Label::new(
+      if let Some(time) = forecast.parse_time(
+        &forecast
+          .current
+          .time,
+      ) {
+        Label::new(
+          cx,
+          format!(
+            "as of {} {}",
+            time.format("%H:%M"),
+            forecast.timezone_abbreviation
+          ),
+        );
+      }
+      WeatherCode::new(
         cx,
-        lookup_weather_text(
-          &forecast
-            .current
-            .weather_code,
-        )
-        .unwrap_or("unknown weather 😱"),
+        forecast
+          .current
+          .weather_code,
+        forecast.is_day_now(),
       );
+      if let Some(condition) = forecast
+        .current
+        .weather_code
+        .and_then(WeatherCondition::from_code)
+      {
+        Label::new(cx, condition.long());
+      }
+      Dashboard::new(cx);
+      WindCompass::new(cx, &forecast);
+      DaylightArc::new(cx, &forecast);
+
+      Label::new(cx, "Next hours").class("title");
+      // the hourly series starts a day back, so skip the hours that are already over
+      let upcoming = forecast.clone();
+      Binding::new(cx, AppData::now, move |cx, now| {
+        let first = upcoming.index_of_now(
+          &upcoming
+            .hourly
+            .time,
+          now.get(cx),
+        );
+        HStack::new(cx, |cx| {
+          for hour in first
+            ..(first + NEXT_HOURS).min(
+              upcoming
+                .hourly
+                .time
+                .len(),
+            )
+          {
+            HourCard::new(cx, &upcoming, hour);
+          }
+        })
+        .class("row");
+      });
+
+      Label::new(cx, "Last and next 24 hours").class("title");
+      HourlyChart::new(cx, &forecast);
     })
   }
 }
 
+/// How many hours ahead the forecast screen shows.
+const NEXT_HOURS: usize = 12;
+
 impl View for ForecastScreen {}
@@ -0,0 +1,74 @@
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// The timezone a forecast's local timestamps are in.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LocalZone {
+  Named(Tz),
+  // NOTE: only when there's no IANA name to go on, since it drifts an hour across a DST change
+  Fixed(FixedOffset),
+}
+
+impl LocalZone {
+  /// `name` as an IANA timezone, or `fallback` when it isn't one.
+  pub fn parse(name: &str, fallback: FixedOffset) -> Self {
+    match name.parse::<Tz>() {
+      Ok(zone) => LocalZone::Named(zone),
+      Err(_) => LocalZone::Fixed(fallback),
+    }
+  }
+
+  /// `time` on the zone's wall clock.
+  pub fn to_local(&self, time: &DateTime<Utc>) -> NaiveDateTime {
+    match self {
+      LocalZone::Named(zone) => time
+        .with_timezone(zone)
+        .naive_local(),
+      LocalZone::Fixed(offset) => time
+        .with_timezone(offset)
+        .naive_local(),
+    }
+  }
+
+  /// The instant a wall-clock `time` in the zone stands for. When the clocks go back and the
+  /// hour happens twice it's the first one, and a time skipped when they go forward is none.
+  pub fn from_local(&self, time: &NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+    match self {
+      LocalZone::Named(zone) => zone
+        .from_local_datetime(time)
+        .earliest()
+        .map(|time| time.fixed_offset()),
+      LocalZone::Fixed(offset) => offset
+        .from_local_datetime(time)
+        .single(),
+    }
+  }
+
+  pub fn offset_at(&self, time: &DateTime<Utc>) -> FixedOffset {
+    match self {
+      LocalZone::Named(zone) => time
+        .with_timezone(zone)
+        .offset()
+        .fix(),
+      LocalZone::Fixed(offset) => *offset,
+    }
+  }
+
+  pub fn name(&self) -> String {
+    match self {
+      LocalZone::Named(zone) => String::from(zone.name()),
+      LocalZone::Fixed(offset) => offset.to_string(),
+    }
+  }
+
+  /// The zone's abbreviation at `time`, e.g. "EDT", or the offset for a fixed one.
+  pub fn abbreviation(&self, time: &DateTime<Utc>) -> String {
+    match self {
+      LocalZone::Named(zone) => time
+        .with_timezone(zone)
+        .format("%Z")
+        .to_string(),
+      LocalZone::Fixed(offset) => format!("UTC{}", offset),
+    }
+  }
+}
@@ -0,0 +1,310 @@
+use serde::{Deserialize, Serialize};
+use vizia::prelude::*;
+
+#[derive(Copy, Clone, Data, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum TemperatureUnit {
+  Celsius,
+  #[default]
+  Fahrenheit,
+}
+
+impl TemperatureUnit {
+  pub const ALL: [TemperatureUnit; 2] = [TemperatureUnit::Celsius, TemperatureUnit::Fahrenheit];
+
+  pub fn query_value(&self) -> &'static str {
+    match self {
+      TemperatureUnit::Celsius => "celsius",
+      TemperatureUnit::Fahrenheit => "fahrenheit",
+    }
+  }
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      TemperatureUnit::Celsius => "°C",
+      TemperatureUnit::Fahrenheit => "°F",
+    }
+  }
+}
+
+#[derive(Copy, Clone, Data, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum WindSpeedUnit {
+  KilometresPerHour,
+  MetresPerSecond,
+  #[default]
+  MilesPerHour,
+  Knots,
+}
+
+impl WindSpeedUnit {
+  pub const ALL: [WindSpeedUnit; 4] = [
+    WindSpeedUnit::KilometresPerHour,
+    WindSpeedUnit::MetresPerSecond,
+    WindSpeedUnit::MilesPerHour,
+    WindSpeedUnit::Knots,
+  ];
+
+  pub fn query_value(&self) -> &'static str {
+    match self {
+      WindSpeedUnit::KilometresPerHour => "kmh",
+      WindSpeedUnit::MetresPerSecond => "ms",
+      WindSpeedUnit::MilesPerHour => "mph",
+      WindSpeedUnit::Knots => "kn",
+    }
+  }
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      WindSpeedUnit::KilometresPerHour => "km/h",
+      WindSpeedUnit::MetresPerSecond => "m/s",
+      WindSpeedUnit::MilesPerHour => "mph",
+      WindSpeedUnit::Knots => "kn",
+    }
+  }
+}
+
+#[derive(Copy, Clone, Data, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum PrecipitationUnit {
+  Millimetres,
+  #[default]
+  Inches,
+}
+
+impl PrecipitationUnit {
+  pub const ALL: [PrecipitationUnit; 2] =
+    [PrecipitationUnit::Millimetres, PrecipitationUnit::Inches];
+
+  pub fn query_value(&self) -> &'static str {
+    match self {
+      PrecipitationUnit::Millimetres => "mm",
+      PrecipitationUnit::Inches => "inch",
+    }
+  }
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      PrecipitationUnit::Millimetres => "mm",
+      PrecipitationUnit::Inches => "inch",
+    }
+  }
+
+  // NOTE: open-meteo reports snowfall in cm when precipitation is in mm
+  pub fn snowfall_label(&self) -> &'static str {
+    match self {
+      PrecipitationUnit::Millimetres => "cm",
+      PrecipitationUnit::Inches => "inch",
+    }
+  }
+}
+
+#[derive(Copy, Clone, Data, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum UnitSystem {
+  Metric,
+  #[default]
+  Imperial,
+  // °C with mph and mm, the way the UK does it
+  Mixed,
+}
+
+impl UnitSystem {
+  pub const ALL: [UnitSystem; 3] = [UnitSystem::Metric, UnitSystem::Imperial, UnitSystem::Mixed];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      UnitSystem::Metric => "Metric",
+      UnitSystem::Imperial => "Imperial",
+      UnitSystem::Mixed => "Mixed",
+    }
+  }
+
+  pub fn temperature(&self) -> TemperatureUnit {
+    match self {
+      UnitSystem::Metric | UnitSystem::Mixed => TemperatureUnit::Celsius,
+      UnitSystem::Imperial => TemperatureUnit::Fahrenheit,
+    }
+  }
+
+  pub fn wind_speed(&self) -> WindSpeedUnit {
+    match self {
+      UnitSystem::Metric => WindSpeedUnit::KilometresPerHour,
+      UnitSystem::Imperial | UnitSystem::Mixed => WindSpeedUnit::MilesPerHour,
+    }
+  }
+
+  pub fn precipitation(&self) -> PrecipitationUnit {
+    match self {
+      UnitSystem::Metric | UnitSystem::Mixed => PrecipitationUnit::Millimetres,
+      UnitSystem::Imperial => PrecipitationUnit::Inches,
+    }
+  }
+}
+
+/// A unit preset plus optional per-quantity overrides.
+#[derive(Copy, Clone, Data, Lens, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct UnitPreferences {
+  pub system: UnitSystem,
+  pub temperature_override: Option<TemperatureUnit>,
+  pub wind_speed_override: Option<WindSpeedUnit>,
+  pub precipitation_override: Option<PrecipitationUnit>,
+}
+
+impl UnitPreferences {
+  pub fn preset(system: UnitSystem) -> Self {
+    Self {
+      system,
+      ..Default::default()
+    }
+  }
+
+  pub fn temperature(&self) -> TemperatureUnit {
+    self
+      .temperature_override
+      .unwrap_or(
+        self
+          .system
+          .temperature(),
+      )
+  }
+
+  pub fn wind_speed(&self) -> WindSpeedUnit {
+    self
+      .wind_speed_override
+      .unwrap_or(
+        self
+          .system
+          .wind_speed(),
+      )
+  }
+
+  pub fn precipitation(&self) -> PrecipitationUnit {
+    self
+      .precipitation_override
+      .unwrap_or(
+        self
+          .system
+          .precipitation(),
+      )
+  }
+
+  pub fn query(&self) -> Vec<(&'static str, String)> {
+    vec![
+      (
+        "temperature_unit",
+        String::from(
+          self
+            .temperature()
+            .query_value(),
+        ),
+      ),
+      (
+        "wind_speed_unit",
+        String::from(
+          self
+            .wind_speed()
+            .query_value(),
+        ),
+      ),
+      (
+        "precipitation_unit",
+        String::from(
+          self
+            .precipitation()
+            .query_value(),
+        ),
+      ),
+    ]
+  }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Quantity {
+  Temperature,
+  WindSpeed,
+  Precipitation,
+  Snowfall,
+}
+
+impl Quantity {
+  /// The unit label open-meteo would report for this quantity under `prefs`.
+  pub fn label(&self, prefs: &UnitPreferences) -> &'static str {
+    match self {
+      Quantity::Temperature => prefs
+        .temperature()
+        .label(),
+      Quantity::WindSpeed => prefs
+        .wind_speed()
+        .label(),
+      Quantity::Precipitation => prefs
+        .precipitation()
+        .label(),
+      Quantity::Snowfall => prefs
+        .precipitation()
+        .snowfall_label(),
+    }
+  }
+}
+
+// INFO: everything is converted through °C, m/s and mm
+fn to_base(value: f64, label: &str) -> Option<(Quantity, f64)> {
+  match label {
+    "°C" => Some((Quantity::Temperature, value)),
+    "°F" => Some((Quantity::Temperature, (value - 32.0) * 5.0 / 9.0)),
+    "km/h" => Some((Quantity::WindSpeed, value / 3.6)),
+    "m/s" => Some((Quantity::WindSpeed, value)),
+    "mph" => Some((Quantity::WindSpeed, value * 0.44704)),
+    "kn" => Some((Quantity::WindSpeed, value * 0.514444)),
+    "mm" => Some((Quantity::Precipitation, value)),
+    "cm" => Some((Quantity::Snowfall, value * 10.0)),
+    "inch" => Some((Quantity::Precipitation, value * 25.4)),
+    _ => None,
+  }
+}
+
+fn from_base(value: f64, label: &str) -> Option<f64> {
+  match label {
+    "°C" => Some(value),
+    "°F" => Some(value * 9.0 / 5.0 + 32.0),
+    "km/h" => Some(value * 3.6),
+    "m/s" => Some(value),
+    "mph" => Some(value / 0.44704),
+    "kn" => Some(value / 0.514444),
+    "mm" => Some(value),
+    "cm" => Some(value / 10.0),
+    "inch" => Some(value / 25.4),
+    _ => None,
+  }
+}
+
+/// Converts `value` between two open-meteo unit labels, e.g. "°F" -> "°C".
+/// Returns `None` if either label is unknown or they measure different things.
+pub fn convert(value: f64, from: &str, to: &str) -> Option<f64> {
+  if from == to {
+    return Some(value);
+  }
+  let (from_quantity, base) = to_base(value, from)?;
+  let (to_quantity, _) = to_base(0.0, to)?;
+  let compatible = from_quantity == to_quantity
+    || matches!(
+      (from_quantity, to_quantity),
+      (Quantity::Precipitation, Quantity::Snowfall) | (Quantity::Snowfall, Quantity::Precipitation)
+    );
+  if !compatible {
+    return None;
+  }
+  from_base(base, to)
+}
+
+/// The 16 compass points, clockwise from north a sector of 22.5° apart.
+pub const CARDINALS: [&str; 16] = [
+  "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW", "NW",
+  "NNW",
+];
+
+/// Which of the `CARDINALS` a bearing in degrees is nearest, as an index into it.
+pub fn compass_sector(degrees: f64) -> usize {
+  (degrees.rem_euclid(360.0) / 22.5).round() as usize % 16
+}
+
+/// The 16-point compass name for a bearing in degrees, e.g. 112° is "ESE".
+pub fn cardinal(degrees: f64) -> &'static str {
+  CARDINALS[compass_sector(degrees)]
+}
@@ -0,0 +1,293 @@
+use vizia::prelude::*;
+
+/// The weather codes Open-Meteo reports, a small subset of WMO 4677 with meanings of its own.
+/// The other providers map their conditions onto the same codes.
+///
+/// INFO: https://open-meteo.com/en/docs, under "WMO Weather interpretation codes"
+#[derive(Copy, Clone, Data, Debug, PartialEq, Eq)]
+pub enum WeatherCondition {
+  ClearSky,
+  MainlyClear,
+  PartlyCloudy,
+  Overcast,
+  Fog,
+  RimeFog,
+  LightDrizzle,
+  ModerateDrizzle,
+  DenseDrizzle,
+  LightFreezingDrizzle,
+  DenseFreezingDrizzle,
+  SlightRain,
+  ModerateRain,
+  HeavyRain,
+  LightFreezingRain,
+  HeavyFreezingRain,
+  SlightSnow,
+  ModerateSnow,
+  HeavySnow,
+  SnowGrains,
+  SlightRainShowers,
+  ModerateRainShowers,
+  ViolentRainShowers,
+  SlightSnowShowers,
+  HeavySnowShowers,
+  Thunderstorm,
+  ThunderstormWithSlightHail,
+  ThunderstormWithHeavyHail,
+}
+
+/// How much a condition should get in the way of someone's plans.
+#[derive(Copy, Clone, Data, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+  Calm,
+  Light,
+  Moderate,
+  Severe,
+}
+
+impl Severity {
+  /// The style class conditions of this severity are drawn with.
+  pub fn class(&self) -> &'static str {
+    match self {
+      Severity::Calm => "calm",
+      Severity::Light => "light",
+      Severity::Moderate => "moderate",
+      Severity::Severe => "severe",
+    }
+  }
+}
+
+impl WeatherCondition {
+  pub const ALL: [WeatherCondition; 28] = [
+    WeatherCondition::ClearSky,
+    WeatherCondition::MainlyClear,
+    WeatherCondition::PartlyCloudy,
+    WeatherCondition::Overcast,
+    WeatherCondition::Fog,
+    WeatherCondition::RimeFog,
+    WeatherCondition::LightDrizzle,
+    WeatherCondition::ModerateDrizzle,
+    WeatherCondition::DenseDrizzle,
+    WeatherCondition::LightFreezingDrizzle,
+    WeatherCondition::DenseFreezingDrizzle,
+    WeatherCondition::SlightRain,
+    WeatherCondition::ModerateRain,
+    WeatherCondition::HeavyRain,
+    WeatherCondition::LightFreezingRain,
+    WeatherCondition::HeavyFreezingRain,
+    WeatherCondition::SlightSnow,
+    WeatherCondition::ModerateSnow,
+    WeatherCondition::HeavySnow,
+    WeatherCondition::SnowGrains,
+    WeatherCondition::SlightRainShowers,
+    WeatherCondition::ModerateRainShowers,
+    WeatherCondition::ViolentRainShowers,
+    WeatherCondition::SlightSnowShowers,
+    WeatherCondition::HeavySnowShowers,
+    WeatherCondition::Thunderstorm,
+    WeatherCondition::ThunderstormWithSlightHail,
+    WeatherCondition::ThunderstormWithHeavyHail,
+  ];
+
+  pub fn from_code(code: i64) -> Option<Self> {
+    Self::ALL
+      .into_iter()
+      .find(|condition| condition.code() == code)
+  }
+
+  /// Hourly and daily codes arrive as floats alongside the other variables.
+  pub fn from_value(value: f64) -> Option<Self> {
+    if value.fract() != 0.0 {
+      return None;
+    }
+    Self::from_code(value as i64)
+  }
+
+  pub fn code(&self) -> i64 {
+    match self {
+      WeatherCondition::ClearSky => 0,
+      WeatherCondition::MainlyClear => 1,
+      WeatherCondition::PartlyCloudy => 2,
+      WeatherCondition::Overcast => 3,
+      WeatherCondition::Fog => 45,
+      WeatherCondition::RimeFog => 48,
+      WeatherCondition::LightDrizzle => 51,
+      WeatherCondition::ModerateDrizzle => 53,
+      WeatherCondition::DenseDrizzle => 55,
+      WeatherCondition::LightFreezingDrizzle => 56,
+      WeatherCondition::DenseFreezingDrizzle => 57,
+      WeatherCondition::SlightRain => 61,
+      WeatherCondition::ModerateRain => 63,
+      WeatherCondition::HeavyRain => 65,
+      WeatherCondition::LightFreezingRain => 66,
+      WeatherCondition::HeavyFreezingRain => 67,
+      WeatherCondition::SlightSnow => 71,
+      WeatherCondition::ModerateSnow => 73,
+      WeatherCondition::HeavySnow => 75,
+      WeatherCondition::SnowGrains => 77,
+      WeatherCondition::SlightRainShowers => 80,
+      WeatherCondition::ModerateRainShowers => 81,
+      WeatherCondition::ViolentRainShowers => 82,
+      WeatherCondition::SlightSnowShowers => 85,
+      WeatherCondition::HeavySnowShowers => 86,
+      WeatherCondition::Thunderstorm => 95,
+      WeatherCondition::ThunderstormWithSlightHail => 96,
+      WeatherCondition::ThunderstormWithHeavyHail => 99,
+    }
+  }
+
+  /// A few words, for cards and labels.
+  pub fn short(&self) -> &'static str {
+    match self {
+      WeatherCondition::ClearSky => "Clear",
+      WeatherCondition::MainlyClear => "Mainly clear",
+      WeatherCondition::PartlyCloudy => "Partly cloudy",
+      WeatherCondition::Overcast => "Overcast",
+      WeatherCondition::Fog => "Fog",
+      WeatherCondition::RimeFog => "Freezing fog",
+      WeatherCondition::LightDrizzle => "Light drizzle",
+      WeatherCondition::ModerateDrizzle => "Drizzle",
+      WeatherCondition::DenseDrizzle => "Heavy drizzle",
+      WeatherCondition::LightFreezingDrizzle => "Light freezing drizzle",
+      WeatherCondition::DenseFreezingDrizzle => "Freezing drizzle",
+      WeatherCondition::SlightRain => "Light rain",
+      WeatherCondition::ModerateRain => "Rain",
+      WeatherCondition::HeavyRain => "Heavy rain",
+      WeatherCondition::LightFreezingRain => "Light freezing rain",
+      WeatherCondition::HeavyFreezingRain => "Freezing rain",
+      WeatherCondition::SlightSnow => "Light snow",
+      WeatherCondition::ModerateSnow => "Snow",
+      WeatherCondition::HeavySnow => "Heavy snow",
+      WeatherCondition::SnowGrains => "Snow grains",
+      WeatherCondition::SlightRainShowers => "Light showers",
+      WeatherCondition::ModerateRainShowers => "Showers",
+      WeatherCondition::ViolentRainShowers => "Violent showers",
+      WeatherCondition::SlightSnowShowers => "Snow showers",
+      WeatherCondition::HeavySnowShowers => "Heavy snow showers",
+      WeatherCondition::Thunderstorm => "Thunderstorm",
+      WeatherCondition::ThunderstormWithSlightHail => "Thunderstorm, hail",
+      WeatherCondition::ThunderstormWithHeavyHail => "Thunderstorm, heavy hail",
+    }
+  }
+
+  /// A full sentence, for when there's room to explain.
+  pub fn long(&self) -> &'static str {
+    match self {
+      WeatherCondition::ClearSky => "Clear sky",
+      WeatherCondition::MainlyClear => "Mainly clear, with a few clouds",
+      WeatherCondition::PartlyCloudy => "Partly cloudy",
+      WeatherCondition::Overcast => "Overcast, the sky is covered in cloud",
+      WeatherCondition::Fog => "Fog",
+      WeatherCondition::RimeFog => "Fog, depositing rime ice on cold surfaces",
+      WeatherCondition::LightDrizzle => "Drizzle of light intensity",
+      WeatherCondition::ModerateDrizzle => "Drizzle of moderate intensity",
+      WeatherCondition::DenseDrizzle => "Drizzle of dense intensity",
+      WeatherCondition::LightFreezingDrizzle => "Freezing drizzle of light intensity",
+      WeatherCondition::DenseFreezingDrizzle => "Freezing drizzle of dense intensity",
+      WeatherCondition::SlightRain => "Rain of slight intensity",
+      WeatherCondition::ModerateRain => "Rain of moderate intensity",
+      WeatherCondition::HeavyRain => "Rain of heavy intensity",
+      WeatherCondition::LightFreezingRain => "Freezing rain of light intensity",
+      WeatherCondition::HeavyFreezingRain => "Freezing rain of heavy intensity",
+      WeatherCondition::SlightSnow => "Snowfall of slight intensity",
+      WeatherCondition::ModerateSnow => "Snowfall of moderate intensity",
+      WeatherCondition::HeavySnow => "Snowfall of heavy intensity",
+      WeatherCondition::SnowGrains => "Snow grains",
+      WeatherCondition::SlightRainShowers => "Slight rain showers",
+      WeatherCondition::ModerateRainShowers => "Moderate rain showers",
+      WeatherCondition::ViolentRainShowers => "Violent rain showers",
+      WeatherCondition::SlightSnowShowers => "Slight snow showers",
+      WeatherCondition::HeavySnowShowers => "Heavy snow showers",
+      WeatherCondition::Thunderstorm => "Slight or moderate thunderstorm",
+      WeatherCondition::ThunderstormWithSlightHail => "Thunderstorm with slight hail",
+      WeatherCondition::ThunderstormWithHeavyHail => "Thunderstorm with heavy hail",
+    }
+  }
+
+  /// The bundled SVG to draw, with the sun or moon in it where the condition leaves room for one.
+  pub fn icon(&self, is_day: bool) -> &'static str {
+    let (day, night) = match self {
+      WeatherCondition::ClearSky => (CLEAR_DAY, CLEAR_NIGHT),
+      WeatherCondition::MainlyClear | WeatherCondition::PartlyCloudy => {
+        (PARTLY_CLOUDY_DAY, PARTLY_CLOUDY_NIGHT)
+      }
+      WeatherCondition::Overcast => (CLOUDY, CLOUDY),
+      WeatherCondition::Fog | WeatherCondition::RimeFog => (FOG, FOG),
+      WeatherCondition::LightDrizzle
+      | WeatherCondition::ModerateDrizzle
+      | WeatherCondition::DenseDrizzle => (DRIZZLE, DRIZZLE),
+      WeatherCondition::LightFreezingDrizzle
+      | WeatherCondition::DenseFreezingDrizzle
+      | WeatherCondition::LightFreezingRain
+      | WeatherCondition::HeavyFreezingRain => (SLEET, SLEET),
+      WeatherCondition::SlightRain
+      | WeatherCondition::ModerateRain
+      | WeatherCondition::HeavyRain => (RAIN, RAIN),
+      WeatherCondition::SlightSnow
+      | WeatherCondition::ModerateSnow
+      | WeatherCondition::HeavySnow
+      | WeatherCondition::SnowGrains
+      | WeatherCondition::SlightSnowShowers
+      | WeatherCondition::HeavySnowShowers => (SNOW, SNOW),
+      WeatherCondition::SlightRainShowers
+      | WeatherCondition::ModerateRainShowers
+      | WeatherCondition::ViolentRainShowers => (SHOWERS_DAY, SHOWERS_NIGHT),
+      WeatherCondition::Thunderstorm
+      | WeatherCondition::ThunderstormWithSlightHail
+      | WeatherCondition::ThunderstormWithHeavyHail => (THUNDERSTORM, THUNDERSTORM),
+    };
+    if is_day {
+      day
+    } else {
+      night
+    }
+  }
+
+  pub fn severity(&self) -> Severity {
+    match self {
+      WeatherCondition::ClearSky
+      | WeatherCondition::MainlyClear
+      | WeatherCondition::PartlyCloudy
+      | WeatherCondition::Overcast => Severity::Calm,
+      WeatherCondition::Fog
+      | WeatherCondition::LightDrizzle
+      | WeatherCondition::ModerateDrizzle
+      | WeatherCondition::SlightRain
+      | WeatherCondition::SlightSnow
+      | WeatherCondition::SnowGrains
+      | WeatherCondition::SlightRainShowers
+      | WeatherCondition::SlightSnowShowers => Severity::Light,
+      WeatherCondition::RimeFog
+      | WeatherCondition::DenseDrizzle
+      | WeatherCondition::LightFreezingDrizzle
+      | WeatherCondition::ModerateRain
+      | WeatherCondition::ModerateSnow
+      | WeatherCondition::ModerateRainShowers
+      | WeatherCondition::HeavySnowShowers
+      | WeatherCondition::Thunderstorm => Severity::Moderate,
+      WeatherCondition::DenseFreezingDrizzle
+      | WeatherCondition::HeavyRain
+      | WeatherCondition::LightFreezingRain
+      | WeatherCondition::HeavyFreezingRain
+      | WeatherCondition::HeavySnow
+      | WeatherCondition::ViolentRainShowers
+      | WeatherCondition::ThunderstormWithSlightHail
+      | WeatherCondition::ThunderstormWithHeavyHail => Severity::Severe,
+    }
+  }
+}
+
+// INFO: line art in the style of https://tabler.io/icons, drawn in the stylesheet's foreground colour
+const CLEAR_DAY: &str = include_str!("icons/clear-day.svg");
+const CLEAR_NIGHT: &str = include_str!("icons/clear-night.svg");
+const PARTLY_CLOUDY_DAY: &str = include_str!("icons/partly-cloudy-day.svg");
+const PARTLY_CLOUDY_NIGHT: &str = include_str!("icons/partly-cloudy-night.svg");
+const CLOUDY: &str = include_str!("icons/cloudy.svg");
+const FOG: &str = include_str!("icons/fog.svg");
+const DRIZZLE: &str = include_str!("icons/drizzle.svg");
+const RAIN: &str = include_str!("icons/rain.svg");
+const SLEET: &str = include_str!("icons/sleet.svg");
+const SNOW: &str = include_str!("icons/snow.svg");
+const SHOWERS_DAY: &str = include_str!("icons/showers-day.svg");
+const SHOWERS_NIGHT: &str = include_str!("icons/showers-night.svg");
+const THUNDERSTORM: &str = include_str!("icons/thunderstorm.svg");
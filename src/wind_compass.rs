@@ -0,0 +1,167 @@
+use std::f32::consts::PI;
+
+use vizia::prelude::*;
+use vizia::vg;
+
+use crate::api_models::Meteo;
+use crate::app_data::AppData;
+use crate::units::{cardinal, compass_sector};
+use crate::views::format_value;
+
+/// How many of `directions` fall in each of the 16 compass sectors, starting from north.
+pub fn wind_rose(directions: &[Option<f64>]) -> [usize; 16] {
+  let mut rose = [0; 16];
+  for degrees in directions
+    .iter()
+    .flatten()
+  {
+    rose[compass_sector(*degrees)] += 1;
+  }
+  rose
+}
+
+/// Where the wind is coming from, how hard it blows, and where it's been coming from over the
+/// hours ahead.
+pub struct WindCompass {}
+
+impl WindCompass {
+  pub fn new(cx: &mut Context, forecast: &Meteo) -> Handle<Self> {
+    let forecast = forecast.clone();
+    Self {}.build(cx, |cx| {
+      // the rose covers the coming day, which moves on with the clock
+      Binding::new(cx, AppData::now, move |cx, now| {
+        let current = &forecast.current;
+        let units = &forecast.current_units;
+        let direction = current.wind_direction_10_m;
+        let first = forecast.index_of_now(
+          &forecast
+            .hourly
+            .time,
+          now.get(cx),
+        );
+        let upcoming = forecast
+          .hourly
+          .wind_direction_10_m
+          .get(first..)
+          .unwrap_or_default();
+        let rose = wind_rose(
+          &upcoming[..upcoming
+            .len()
+            .min(24)],
+        );
+
+        HStack::new(cx, |cx| {
+          CompassCanvas { direction, rose }
+            .build(cx, |_| {})
+            .class("compass");
+          VStack::new(cx, |cx| {
+            Label::new(
+              cx,
+              match direction {
+                Some(degrees) => format!("From the {} ({}°)", cardinal(degrees), degrees.round()),
+                None => String::from("No wind direction"),
+              },
+            );
+            Label::new(
+              cx,
+              format!(
+                "{}, gusts {}",
+                format_value(current.wind_speed_10_m, &units.wind_speed_10_m),
+                format_value(current.wind_gusts_10_m, &units.wind_gusts_10_m),
+              ),
+            );
+          });
+        })
+        .class("row");
+      });
+    })
+  }
+}
+
+impl View for WindCompass {}
+
+struct CompassCanvas {
+  /// Meteorological, so the bearing the wind comes from.
+  direction: Option<f64>,
+  rose: [usize; 16],
+}
+
+impl View for CompassCanvas {
+  fn draw(&self, cx: &mut DrawContext, canvas: &vg::Canvas) {
+    let bounds = cx.bounds();
+    let scale = cx.scale_factor();
+    let (center_x, center_y) = (bounds.x + bounds.w / 2.0, bounds.y + bounds.h / 2.0);
+    let radius = bounds
+      .w
+      .min(bounds.h)
+      / 2.0
+      - 2.0 * scale;
+    // compass bearings run clockwise from north, which is up
+    let point = |degrees: f32, distance: f32| {
+      let angle = degrees.to_radians() - PI / 2.0;
+      (
+        center_x + distance * angle.cos(),
+        center_y + distance * angle.sin(),
+      )
+    };
+
+    let mut stroke = vg::Paint::default();
+    stroke.set_anti_alias(true);
+    stroke.set_style(vg::PaintStyle::Stroke);
+    stroke.set_stroke_width(1.0 * scale);
+    stroke.set_color(vg::Color::from_argb(255, 168, 153, 132));
+    canvas.draw_circle((center_x, center_y), radius, &stroke);
+    let mut ticks = vg::Path::new();
+    for quarter in 0..4 {
+      let degrees = quarter as f32 * 90.0;
+      ticks.move_to(point(degrees, radius));
+      ticks.line_to(point(degrees, radius * 0.85));
+    }
+    canvas.draw_path(&ticks, &stroke);
+
+    // each petal reaches out as far as the share of hours the wind comes from that way
+    let most = self
+      .rose
+      .iter()
+      .copied()
+      .max()
+      .unwrap_or(0);
+    if most > 0 {
+      let mut petals = vg::Paint::default();
+      petals.set_anti_alias(true);
+      petals.set_color(vg::Color::from_argb(96, 131, 165, 152));
+      for (sector, count) in self
+        .rose
+        .iter()
+        .enumerate()
+      {
+        if *count == 0 {
+          continue;
+        }
+        let degrees = sector as f32 * 22.5;
+        let length = radius * 0.8 * *count as f32 / most as f32;
+        let mut petal = vg::Path::new();
+        petal.move_to((center_x, center_y));
+        petal.line_to(point(degrees - 9.0, length));
+        petal.line_to(point(degrees + 9.0, length));
+        petal.close();
+        canvas.draw_path(&petal, &petals);
+      }
+    }
+
+    if let Some(direction) = self.direction {
+      // the arrow points downwind, the way the air is moving
+      let heading = direction as f32 + 180.0;
+      let mut arrow = vg::Paint::default();
+      arrow.set_anti_alias(true);
+      arrow.set_color(vg::Color::from_argb(255, 250, 189, 47));
+      let mut path = vg::Path::new();
+      path.move_to(point(heading, radius * 0.75));
+      path.line_to(point(heading + 150.0, radius * 0.3));
+      path.line_to(point(heading + 180.0, radius * 0.5));
+      path.line_to(point(heading - 150.0, radius * 0.3));
+      path.close();
+      canvas.draw_path(&path, &arrow);
+    }
+  }
+}
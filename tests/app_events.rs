@@ -0,0 +1,471 @@
+mod common;
+
+use std::thread;
+use std::time::Duration;
+
+use chrono::{TimeDelta, Utc};
+use common::{fixtures_dir, forecast_fixture};
+use rain::api_models::Meteo;
+use rain::app_data::{AppData, AppEvent, Screen};
+use rain::db_models::HistoricalForecast;
+use rain::http::{FetchError, HttpClient, HttpMode};
+use rain::providers::ProviderKind;
+use rain::queries::*;
+use rain::units::{UnitPreferences, UnitSystem};
+use tokio::runtime::Runtime;
+use vizia::prelude::*;
+
+const GEOHASH: &str = "dr5regw3p";
+
+/// Runs `test` against a fresh in-memory database, with the app replaying recorded responses.
+fn with_app(test: impl FnOnce(&Runtime, &Repository, AppData)) {
+  // background work runs on whichever runtime is current, the same way it does under
+  // `#[tokio::main]`
+  let rt = Runtime::new().unwrap();
+  let _guard = rt.enter();
+  let repo = rt
+    .block_on(Repository::in_memory())
+    .unwrap();
+
+  let app = AppData {
+    http: HttpClient::new(HttpMode::Replay(fixtures_dir())),
+    ..AppData::new(repo.clone())
+  };
+  test(&rt, &repo, app);
+}
+
+fn dispatch(app: &mut AppData, event: AppEvent) {
+  let mut cx = Context::default();
+  let mut ex = EventContext::new(&mut cx);
+  app.event(&mut ex, &mut Event::new(event));
+}
+
+/// Background work posts its results through an event loop the test doesn't run, so watch for
+/// its side effects instead.
+fn eventually<T>(mut check: impl FnMut() -> Option<T>) -> T {
+  for _ in 0..100 {
+    if let Some(value) = check() {
+      return value;
+    }
+    thread::sleep(Duration::from_millis(50));
+  }
+  panic!("background work never finished");
+}
+
+#[test]
+fn confirm_location_makes_it_current_and_resolves_its_timezone() {
+  with_app(|rt, repo, mut app| {
+    let location = rt
+      .block_on(repo.save_location("Home", GEOHASH, ProviderKind::OpenMeteo))
+      .unwrap();
+
+    dispatch(&mut app, AppEvent::ConfirmLocation(location.clone()));
+
+    assert!(app.location_confirmed);
+    assert!(app.loading);
+    assert_eq!(app.saved_location, Some(location.clone()));
+    let location = eventually(|| {
+      rt.block_on(repo.get_current_location())
+        .ok()
+        .filter(|location| {
+          location
+            .timezone
+            .is_some()
+        })
+    });
+    assert_eq!(location.geohash, GEOHASH);
+    assert_eq!(location.timezone(), "America/New_York");
+  });
+}
+
+#[test]
+fn refresh_forecast_stores_the_fetched_forecast() {
+  with_app(|rt, repo, mut app| {
+    let location = rt
+      .block_on(repo.save_location("Home", GEOHASH, ProviderKind::OpenMeteo))
+      .unwrap();
+    dispatch(
+      &mut app,
+      AppEvent::Rehydrate(location.clone(), HistoricalForecast::default()),
+    );
+    assert_eq!(app.saved_location, Some(location.clone()));
+
+    dispatch(&mut app, AppEvent::RefreshForecast);
+    assert!(app.loading);
+
+    let stored = eventually(|| {
+      rt.block_on(repo.get_latest_historical_forecast(location.id))
+        .ok()
+    });
+    let meteo: Meteo = serde_json::from_str(&stored.response).unwrap();
+    assert_eq!(
+      meteo
+        .current
+        .temperature_2_m,
+      Some(71.6)
+    );
+    let location = rt
+      .block_on(repo.get_latest_location())
+      .unwrap();
+    assert_eq!(location.timezone(), "America/New_York");
+  });
+}
+
+#[test]
+fn refresh_forecast_uses_the_location_provider() {
+  with_app(|rt, repo, mut app| {
+    let location = rt
+      .block_on(repo.save_location("Oslo", "u4xsudvx", ProviderKind::MetNorway))
+      .unwrap();
+    dispatch(
+      &mut app,
+      AppEvent::Rehydrate(location.clone(), HistoricalForecast::default()),
+    );
+
+    dispatch(&mut app, AppEvent::RefreshForecast);
+
+    let stored = eventually(|| {
+      rt.block_on(repo.get_latest_historical_forecast(location.id))
+        .ok()
+    });
+    let meteo: Meteo = serde_json::from_str(&stored.response).unwrap();
+    // no stored timezone yet, so the times are in an offset estimated from the longitude
+    assert_eq!(meteo.timezone, "+01:00");
+    // °C from MET Norway, converted to the default imperial units
+    assert_eq!(
+      meteo
+        .current_units
+        .temperature_2_m,
+      "°F"
+    );
+    // and the estimate isn't kept as the location's timezone
+    let location = rt
+      .block_on(repo.get_latest_location())
+      .unwrap();
+    assert_eq!(location.timezone, None);
+  });
+}
+
+#[test]
+fn ticks_keep_the_clock_current() {
+  with_app(|_rt, _repo, mut app| {
+    let later = Utc::now() + TimeDelta::minutes(5);
+
+    dispatch(&mut app, AppEvent::Tick(later));
+
+    assert_eq!(app.now, later);
+  });
+}
+
+#[test]
+fn set_units_converts_the_forecast_on_screen() {
+  with_app(|_rt, _repo, mut app| {
+    dispatch(&mut app, AppEvent::SetWeatherData(Some(forecast_fixture())));
+
+    dispatch(
+      &mut app,
+      AppEvent::SetUnits(UnitPreferences::preset(UnitSystem::Metric)),
+    );
+
+    let meteo = app
+      .weather_data
+      .unwrap();
+    assert_eq!(
+      meteo
+        .current_units
+        .temperature_2_m,
+      "°C"
+    );
+    assert!(
+      (meteo
+        .current
+        .temperature_2_m
+        .unwrap()
+        - 22.0)
+        .abs()
+        < 0.01
+    );
+    assert_eq!(
+      meteo
+        .daily_units
+        .precipitation_sum,
+      "mm"
+    );
+  });
+}
+
+#[test]
+fn stale_forecasts_are_dropped() {
+  with_app(|rt, repo, mut app| {
+    let location = rt
+      .block_on(repo.save_location("Home", GEOHASH, ProviderKind::OpenMeteo))
+      .unwrap();
+    dispatch(
+      &mut app,
+      AppEvent::Rehydrate(location.clone(), HistoricalForecast::default()),
+    );
+
+    dispatch(&mut app, AppEvent::RefreshForecast);
+    dispatch(&mut app, AppEvent::RefreshForecast);
+    assert_eq!(app.refresh_generation, 2);
+
+    // the first refresh loses the race
+    dispatch(&mut app, AppEvent::ForecastLoaded(1, forecast_fixture()));
+    assert!(app.loading);
+    assert_eq!(app.weather_data, None);
+
+    dispatch(&mut app, AppEvent::ForecastLoaded(2, forecast_fixture()));
+    assert!(!app.loading);
+    assert!(app
+      .weather_data
+      .is_some());
+
+    // let the real fetches land before the database goes away
+    eventually(|| {
+      rt.block_on(repo.get_latest_historical_forecast(location.id))
+        .ok()
+    });
+  });
+}
+
+#[test]
+fn failed_refresh_keeps_the_forecast_and_reports_why() {
+  with_app(|rt, repo, mut app| {
+    // no recorded responses for this location, so the fetch fails
+    let location = rt
+      .block_on(repo.save_location("Nowhere", "s0000000", ProviderKind::Nws))
+      .unwrap();
+    dispatch(&mut app, AppEvent::SetWeatherData(Some(forecast_fixture())));
+    dispatch(
+      &mut app,
+      AppEvent::Rehydrate(location, HistoricalForecast::default()),
+    );
+
+    dispatch(&mut app, AppEvent::RefreshForecast);
+    dispatch(
+      &mut app,
+      AppEvent::FailedToRetrieveForecast(1, FetchError::Status(503)),
+    );
+
+    assert!(!app.loading);
+    assert_eq!(app.fetch_error, Some(FetchError::Status(503)));
+    assert!(app
+      .weather_data
+      .is_some());
+
+    dispatch(&mut app, AppEvent::DismissError);
+    assert_eq!(app.fetch_error, None);
+  });
+}
+
+#[test]
+fn rehydrate_shows_the_cached_forecast() {
+  with_app(|rt, repo, mut app| {
+    let location = rt
+      .block_on(repo.save_location("Home", GEOHASH, ProviderKind::OpenMeteo))
+      .unwrap();
+    rt.block_on(repo.add_forecast_to_db(&location, &forecast_fixture(), Utc::now()))
+      .unwrap();
+    let cached = rt
+      .block_on(repo.get_latest_historical_forecast(location.id))
+      .unwrap();
+
+    dispatch(
+      &mut app,
+      AppEvent::SetUnits(UnitPreferences::preset(UnitSystem::Metric)),
+    );
+    dispatch(&mut app, AppEvent::Rehydrate(location, cached));
+
+    let meteo = app
+      .weather_data
+      .unwrap();
+    assert_eq!(
+      meteo
+        .current_units
+        .temperature_2_m,
+      "°C"
+    );
+    assert!(app
+      .fetched_at
+      .is_some());
+  });
+}
+
+#[test]
+fn selecting_a_location_switches_to_it_and_remembers_it() {
+  with_app(|rt, repo, mut app| {
+    for (name, geohash) in [("Home", GEOHASH), ("Work", "dr5ru7")] {
+      rt.block_on(repo.save_location(name, geohash, ProviderKind::OpenMeteo))
+        .unwrap();
+    }
+    let locations = rt
+      .block_on(repo.get_all_locations())
+      .unwrap();
+    dispatch(&mut app, AppEvent::SetLocations(locations.clone()));
+    dispatch(&mut app, AppEvent::SetLocation(locations[0].clone()));
+    dispatch(&mut app, AppEvent::SetWeatherData(Some(forecast_fixture())));
+
+    dispatch(&mut app, AppEvent::SelectLocation(locations[1].id));
+
+    assert_eq!(app.saved_location, Some(locations[1].clone()));
+    // the old location's forecast doesn't stay on screen under the new name
+    assert_eq!(app.weather_data, None);
+    assert_eq!(app.screen, Screen::Forecast);
+    eventually(|| {
+      rt.block_on(repo.get_current_location())
+        .ok()
+        .filter(|location| location.id == locations[1].id)
+    });
+  });
+}
+
+#[test]
+fn a_late_cached_forecast_for_another_location_is_dropped() {
+  with_app(|rt, repo, mut app| {
+    let home = rt
+      .block_on(repo.save_location("Home", GEOHASH, ProviderKind::OpenMeteo))
+      .unwrap();
+    rt.block_on(repo.add_forecast_to_db(&home, &forecast_fixture(), Utc::now()))
+      .unwrap();
+    let cached = rt
+      .block_on(repo.get_latest_historical_forecast(home.id))
+      .unwrap();
+    let work = rt
+      .block_on(repo.save_location("Work", "dr5ru7", ProviderKind::OpenMeteo))
+      .unwrap();
+    dispatch(&mut app, AppEvent::SetLocation(work.clone()));
+
+    // looked up when Home was selected, arriving after the switch to Work
+    dispatch(&mut app, AppEvent::Rehydrate(home, cached));
+
+    assert_eq!(app.saved_location, Some(work));
+    assert_eq!(app.weather_data, None);
+  });
+}
+
+#[test]
+fn losing_the_current_location_clears_its_forecast() {
+  with_app(|rt, repo, mut app| {
+    for (name, geohash) in [("Home", GEOHASH), ("Work", "dr5ru7")] {
+      rt.block_on(repo.save_location(name, geohash, ProviderKind::OpenMeteo))
+        .unwrap();
+    }
+    let locations = rt
+      .block_on(repo.get_all_locations())
+      .unwrap();
+    dispatch(&mut app, AppEvent::SetLocations(locations.clone()));
+    dispatch(&mut app, AppEvent::SetLocation(locations[1].clone()));
+    dispatch(&mut app, AppEvent::SetWeatherData(Some(forecast_fixture())));
+
+    let mut renamed = locations[0].clone();
+    renamed.name = String::from("Old home");
+    dispatch(&mut app, AppEvent::SetLocations(vec![renamed.clone()]));
+
+    assert_eq!(app.locations, vec![renamed]);
+    assert_eq!(app.saved_location, None);
+    assert_eq!(app.weather_data, None);
+  });
+}
+
+#[test]
+fn refresh_serves_a_fresh_cached_forecast() {
+  with_app(|rt, repo, mut app| {
+    let location = rt
+      .block_on(repo.save_location("Home", GEOHASH, ProviderKind::OpenMeteo))
+      .unwrap();
+    rt.block_on(repo.add_forecast_to_db(&location, &forecast_fixture(), Utc::now()))
+      .unwrap();
+    let cached = rt
+      .block_on(repo.get_latest_historical_forecast(location.id))
+      .unwrap();
+    dispatch(
+      &mut app,
+      AppEvent::Rehydrate(location.clone(), cached.clone()),
+    );
+
+    dispatch(&mut app, AppEvent::RefreshForecast);
+    assert!(!app.loading);
+    assert_eq!(app.refresh_generation, 0);
+
+    dispatch(&mut app, AppEvent::ForceRefresh);
+    assert!(app.loading);
+    assert_eq!(app.refresh_generation, 1);
+    eventually(|| {
+      rt.block_on(repo.get_latest_historical_forecast(location.id))
+        .ok()
+        .filter(|latest| latest.id != cached.id)
+    });
+  });
+}
+
+#[test]
+fn a_due_tick_refetches_even_a_fresh_forecast() {
+  with_app(|rt, repo, mut app| {
+    let location = rt
+      .block_on(repo.save_location("Home", GEOHASH, ProviderKind::OpenMeteo))
+      .unwrap();
+    rt.block_on(repo.add_forecast_to_db(&location, &forecast_fixture(), Utc::now()))
+      .unwrap();
+    let cached = rt
+      .block_on(repo.get_latest_historical_forecast(location.id))
+      .unwrap();
+    dispatch(
+      &mut app,
+      AppEvent::Rehydrate(location.clone(), cached.clone()),
+    );
+
+    dispatch(&mut app, AppEvent::Tick(Utc::now()));
+
+    assert!(app.loading);
+    eventually(|| {
+      rt.block_on(repo.get_latest_historical_forecast(location.id))
+        .ok()
+        .filter(|latest| latest.id != cached.id)
+    });
+  });
+}
+
+#[test]
+fn ticks_with_nothing_to_refresh_dont_start_the_wait() {
+  with_app(|rt, repo, mut app| {
+    let now = Utc::now();
+    dispatch(&mut app, AppEvent::Tick(now));
+    assert!(!app.loading);
+
+    let location = rt
+      .block_on(repo.save_location("Home", GEOHASH, ProviderKind::OpenMeteo))
+      .unwrap();
+    dispatch(&mut app, AppEvent::SetLocation(location));
+
+    // a minute on is well inside the interval, but nothing was fetched last time
+    dispatch(&mut app, AppEvent::Tick(now + TimeDelta::minutes(1)));
+    assert!(app.loading);
+  });
+}
+
+#[test]
+fn refresh_refetches_a_stale_cached_forecast() {
+  with_app(|rt, repo, mut app| {
+    let location = rt
+      .block_on(repo.save_location("Home", GEOHASH, ProviderKind::OpenMeteo))
+      .unwrap();
+    let an_hour_ago = Utc::now() - TimeDelta::minutes(61);
+    rt.block_on(repo.add_forecast_to_db(&location, &forecast_fixture(), an_hour_ago))
+      .unwrap();
+    let cached = rt
+      .block_on(repo.get_latest_historical_forecast(location.id))
+      .unwrap();
+    dispatch(
+      &mut app,
+      AppEvent::Rehydrate(location.clone(), cached.clone()),
+    );
+
+    dispatch(&mut app, AppEvent::RefreshForecast);
+    assert!(app.loading);
+    eventually(|| {
+      rt.block_on(repo.get_latest_historical_forecast(location.id))
+        .ok()
+        .filter(|latest| latest.id != cached.id)
+    });
+  });
+}
@@ -0,0 +1,125 @@
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, TimeZone, Utc};
+use rain::api_models::Meteo;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Recorded responses, laid out the way `HttpMode::Replay` looks them up.
+pub fn fixtures_dir() -> PathBuf {
+  PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+pub fn fixture(path: &str) -> PathBuf {
+  fixtures_dir().join(path)
+}
+
+/// The recorded Open-Meteo forecast most tests work from: New York on 2024-09-21 at 14:15.
+pub fn forecast_fixture() -> Meteo {
+  serde_json::from_str(&fs::read_to_string(fixture("api.open-meteo.com/v1/forecast.json")).unwrap())
+    .unwrap()
+}
+
+/// When the forecast fixture's current conditions are from, 14:15 in New York.
+pub fn fixture_now() -> DateTime<Utc> {
+  Utc
+    .with_ymd_and_hms(2024, 9, 21, 18, 15, 0)
+    .unwrap()
+}
+
+/// A tiny HTTP server that answers each request with the fixture registered for the longest
+/// matching path prefix, standing in for the real weather APIs.
+pub struct StandIn {
+  pub url: String,
+  requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl StandIn {
+  pub async fn serve(routes: Vec<(&'static str, PathBuf)>) -> Self {
+    let listener = TcpListener::bind("127.0.0.1:0")
+      .await
+      .expect("failed to bind stand-in server");
+    let url = format!(
+      "http://{}",
+      listener
+        .local_addr()
+        .expect("stand-in server has no address")
+    );
+    let requests = Arc::new(Mutex::new(vec![]));
+    let seen = requests.clone();
+
+    tokio::spawn(async move {
+      while let Ok((mut socket, _)) = listener
+        .accept()
+        .await
+      {
+        let mut buffer = vec![0; 16 * 1024];
+        let mut read = 0;
+        while !buffer[..read]
+          .windows(4)
+          .any(|window| window == b"\r\n\r\n")
+        {
+          match socket
+            .read(&mut buffer[read..])
+            .await
+          {
+            Ok(0) | Err(_) => break,
+            Ok(n) => read += n,
+          }
+        }
+        let request = String::from_utf8_lossy(&buffer[..read]);
+        let target = request
+          .split_whitespace()
+          .nth(1)
+          .unwrap_or("/")
+          .to_string();
+        let path = target
+          .split('?')
+          .next()
+          .unwrap_or("/");
+        seen
+          .lock()
+          .unwrap()
+          .push(target.clone());
+
+        let body = routes
+          .iter()
+          .filter(|(prefix, _)| path.starts_with(prefix))
+          .max_by_key(|(prefix, _)| prefix.len())
+          .and_then(|(_, file)| std::fs::read(file).ok());
+        let response = match body {
+          Some(body) => {
+            let mut response = format!(
+              "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+              body.len()
+            )
+            .into_bytes();
+            response.extend(body);
+            response
+          }
+          None => {
+            b"HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n".to_vec()
+          }
+        };
+        let _ = socket
+          .write_all(&response)
+          .await;
+      }
+    });
+
+    Self { url, requests }
+  }
+
+  /// Request targets (path and query) in the order they arrived.
+  pub fn requests(&self) -> Vec<String> {
+    self
+      .requests
+      .lock()
+      .unwrap()
+      .clone()
+  }
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use chrono::{TimeDelta, TimeZone, Utc};
+use common::{fixture_now, forecast_fixture};
+use rain::api_models::Meteo;
+use rain::daylight_arc::{describe_change, format_daylight, Daylight, Sun};
+
+/// The fixture with yesterday in front of today in the daily series.
+fn with_yesterday(daylight_duration: [Option<f64>; 2]) -> Meteo {
+  let mut meteo = forecast_fixture();
+  meteo
+    .daily
+    .time = vec![String::from("2024-09-20"), String::from("2024-09-21")];
+  meteo
+    .daily
+    .sunrise = vec![
+    String::from("2024-09-20T06:46"),
+    String::from("2024-09-21T06:47"),
+  ];
+  meteo
+    .daily
+    .sunset = vec![
+    String::from("2024-09-20T19:01"),
+    String::from("2024-09-21T18:59"),
+  ];
+  meteo
+    .daily
+    .daylight_duration = daylight_duration.to_vec();
+  meteo
+}
+
+#[test]
+fn the_sun_is_placed_between_sunrise_and_sunset() {
+  let daylight = Daylight::from_meteo(&forecast_fixture(), fixture_now()).unwrap();
+
+  assert_eq!(
+    daylight
+      .sunrise
+      .unwrap()
+      .to_string(),
+    "2024-09-21 06:47:00"
+  );
+  assert_eq!(daylight.duration, Some(43679.51));
+  // 14:15 is 448 of the 732 minutes between 06:47 and 18:59
+  let Some(Sun::Up(progress)) = daylight.sun else {
+    panic!("expected the sun to be up, got {:?}", daylight.sun);
+  };
+  assert!((progress - 448.0 / 732.0).abs() < 1e-9);
+  // the fixture's daily series starts today, so there's nothing to compare with
+  assert_eq!(daylight.change, None);
+}
+
+#[test]
+fn before_sunrise_and_after_sunset() {
+  let meteo = forecast_fixture();
+  // 05:00 and 21:30 in New York
+  assert_eq!(
+    Daylight::from_meteo(
+      &meteo,
+      Utc
+        .with_ymd_and_hms(2024, 9, 21, 9, 0, 0)
+        .unwrap()
+    )
+    .unwrap()
+    .sun,
+    Some(Sun::BeforeSunrise)
+  );
+  assert_eq!(
+    Daylight::from_meteo(
+      &meteo,
+      Utc
+        .with_ymd_and_hms(2024, 9, 22, 1, 30, 0)
+        .unwrap()
+    )
+    .unwrap()
+    .sun,
+    Some(Sun::AfterSunset)
+  );
+}
+
+#[test]
+fn the_sun_follows_the_clock_not_the_forecast() {
+  // a forecast fetched at 14:15 and still on screen at 17:15
+  let daylight =
+    Daylight::from_meteo(&forecast_fixture(), fixture_now() + TimeDelta::hours(3)).unwrap();
+
+  assert_eq!(daylight.sun, Some(Sun::Up(628.0 / 732.0)));
+}
+
+#[test]
+fn change_is_measured_against_the_day_before() {
+  let daylight = Daylight::from_meteo(
+    &with_yesterday([Some(43897.0), Some(43679.51)]),
+    fixture_now(),
+  )
+  .unwrap();
+
+  assert!(
+    (daylight
+      .change
+      .unwrap()
+      + 217.49)
+      .abs()
+      < 1e-9
+  );
+  assert_eq!(
+    describe_change(
+      daylight
+        .change
+        .unwrap()
+    ),
+    "3m 37s less than yesterday"
+  );
+}
+
+#[test]
+fn polar_day_and_night_dont_need_a_sunrise() {
+  let mut meteo = with_yesterday([Some(86400.0), Some(86400.0)]);
+  meteo
+    .daily
+    .sunrise = vec![];
+  meteo
+    .daily
+    .sunset = vec![];
+  let daylight = Daylight::from_meteo(&meteo, fixture_now()).unwrap();
+  assert_eq!(daylight.sun, Some(Sun::PolarDay(51300.0 / 86400.0)));
+  assert_eq!(daylight.change, Some(0.0));
+
+  meteo
+    .daily
+    .daylight_duration = vec![Some(0.0), Some(0.0)];
+  let daylight = Daylight::from_meteo(&meteo, fixture_now()).unwrap();
+  assert_eq!(daylight.sun, Some(Sun::PolarNight));
+  assert_eq!(daylight.sunrise, None);
+}
+
+#[test]
+fn missing_days_and_durations_are_not_an_error() {
+  let mut meteo = with_yesterday([None, None]);
+  // falls back on the gap between sunrise and sunset
+  assert_eq!(
+    Daylight::from_meteo(&meteo, fixture_now())
+      .unwrap()
+      .duration,
+    Some(43920.0)
+  );
+
+  meteo
+    .daily
+    .sunrise = vec![String::from("bogus")];
+  let daylight = Daylight::from_meteo(&meteo, fixture_now()).unwrap();
+  assert_eq!(daylight.duration, None);
+  assert_eq!(daylight.sun, None);
+
+  // a day the forecast doesn't cover
+  assert_eq!(
+    Daylight::from_meteo(&meteo, fixture_now() + TimeDelta::days(10)),
+    None
+  );
+}
+
+#[test]
+fn durations_are_formatted() {
+  assert_eq!(format_daylight(43679.51), "12h 08m");
+  assert_eq!(format_daylight(0.0), "0h 00m");
+  assert_eq!(format_daylight(86400.0), "24h 00m");
+  assert_eq!(describe_change(42.4), "42s more than yesterday");
+  assert_eq!(describe_change(0.2), "the same as yesterday");
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use chrono::TimeDelta;
+use common::{fixture_now, forecast_fixture};
+use rain::hourly_chart::ChartData;
+
+#[test]
+fn now_falls_between_the_hours() {
+  // the series starts at 13:00 and the clock says 14:15
+  let data = ChartData::from_meteo(&forecast_fixture(), fixture_now());
+
+  assert_eq!(data.hours(), 3);
+  assert_eq!(data.now, Some(1.25));
+  assert_eq!(data.x(0.0), ChartData::HOUR_WIDTH / 2.0);
+}
+
+#[test]
+fn the_now_line_follows_the_clock() {
+  let data = ChartData::from_meteo(&forecast_fixture(), fixture_now() + TimeDelta::minutes(45));
+
+  assert_eq!(data.now, Some(2.0));
+}
+
+#[test]
+fn hovering_picks_the_column_under_the_pointer() {
+  let data = ChartData::from_meteo(&forecast_fixture(), fixture_now());
+
+  assert_eq!(data.hour_at(-1.0), None);
+  assert_eq!(data.hour_at(0.0), Some(0));
+  assert_eq!(data.hour_at(ChartData::HOUR_WIDTH * 2.5), Some(2));
+  assert_eq!(data.hour_at(data.width()), None);
+  assert!(data
+    .describe(1)
+    .starts_with("14:00"));
+}
+
+#[test]
+fn a_flat_or_empty_series_still_has_a_range() {
+  let mut data = ChartData::from_meteo(&forecast_fixture(), fixture_now());
+  data.temperature = vec![Some(70.0), None, Some(70.0)];
+  assert_eq!(data.temperature_range(), Some((69.5, 70.5)));
+
+  data.temperature = vec![None, None, None];
+  assert_eq!(data.temperature_range(), None);
+}
@@ -0,0 +1,493 @@
+mod common;
+
+use common::{fixture, StandIn};
+use rain::http::{FetchError, HttpClient, HttpMode};
+use rain::providers::met_norway::MetNorway;
+use rain::providers::nws::Nws;
+use rain::providers::open_meteo::OpenMeteo;
+use rain::providers::{ForecastRequest, WeatherProvider};
+use rain::units::{UnitPreferences, UnitSystem};
+
+fn live() -> HttpClient {
+  HttpClient::new(HttpMode::Live)
+}
+
+fn request(system: UnitSystem) -> ForecastRequest {
+  ForecastRequest {
+    lat: 40.7128,
+    lng: -74.006,
+    timezone: String::from("auto"),
+    units: UnitPreferences::preset(system),
+    forecast_days: 7,
+  }
+}
+
+#[tokio::test]
+async fn open_meteo_parses_forecast_and_sends_units() {
+  let server = StandIn::serve(vec![(
+    "/v1/forecast",
+    fixture("api.open-meteo.com/v1/forecast.json"),
+  )])
+  .await;
+  let provider = OpenMeteo::new(format!("{}/v1/forecast", server.url), live());
+
+  let meteo = provider
+    .fetch(&request(UnitSystem::Imperial))
+    .await
+    .unwrap();
+
+  assert_eq!(meteo.timezone, "America/New_York");
+  assert_eq!(
+    meteo
+      .current
+      .temperature_2_m,
+    Some(71.6)
+  );
+  assert_eq!(
+    meteo
+      .current_units
+      .temperature_2_m,
+    "°F"
+  );
+  assert_eq!(
+    meteo
+      .current
+      .weather_code,
+    Some(2)
+  );
+  assert_eq!(
+    meteo
+      .hourly
+      .dew_point_2_m[0],
+    Some(56.3)
+  );
+  assert_eq!(
+    meteo
+      .hourly
+      .precipitation_probability[2],
+    None
+  );
+  assert_eq!(
+    meteo
+      .daily
+      .time
+      .len(),
+    2
+  );
+  assert_eq!(
+    meteo
+      .daily
+      .precipitation_probability_max[1],
+    None
+  );
+
+  let requests = server.requests();
+  assert_eq!(requests.len(), 1);
+  assert!(requests[0].contains("temperature_unit=fahrenheit"));
+  assert!(requests[0].contains("timezone=auto"));
+  assert!(requests[0].contains("forecast_days=7"));
+}
+
+#[tokio::test]
+async fn met_norway_maps_compact_forecast() {
+  let server = StandIn::serve(vec![(
+    "/weatherapi/locationforecast/2.0/compact",
+    fixture("api.met.no/weatherapi/locationforecast/2.0/compact.json"),
+  )])
+  .await;
+  let provider = MetNorway::new(
+    format!("{}/weatherapi/locationforecast/2.0/compact", server.url),
+    live(),
+  );
+
+  let oslo = ForecastRequest {
+    lat: 59.9139,
+    lng: 10.7522,
+    timezone: String::from("Europe/Oslo"),
+    ..request(UnitSystem::Metric)
+  };
+
+  let meteo = provider
+    .fetch(&oslo)
+    .await
+    .unwrap();
+
+  assert_eq!(meteo.latitude, 59.9139);
+  // MET Norway answers in UTC, shown in the location's own timezone
+  assert_eq!(meteo.timezone, "Europe/Oslo");
+  assert_eq!(meteo.timezone_abbreviation, "CEST");
+  assert_eq!(meteo.utc_offset_seconds, 7200.0);
+  assert_eq!(
+    meteo
+      .current
+      .time,
+    "2024-09-21T15:00"
+  );
+  assert_eq!(
+    meteo
+      .current
+      .temperature_2_m,
+    Some(15.2)
+  );
+  assert_eq!(
+    meteo
+      .current_units
+      .temperature_2_m,
+    "°C"
+  );
+  assert_eq!(
+    meteo
+      .current
+      .weather_code,
+    Some(61)
+  );
+  assert_eq!(
+    meteo
+      .current
+      .is_day,
+    Some(1.0)
+  );
+  assert_eq!(
+    meteo
+      .current
+      .apparent_temperature,
+    None
+  );
+  // 3.4 m/s
+  assert_eq!(
+    meteo
+      .current_units
+      .wind_speed_10_m,
+    "km/h"
+  );
+  assert!(
+    (meteo
+      .current
+      .wind_speed_10_m
+      .unwrap()
+      - 12.24)
+      .abs()
+      < 0.01
+  );
+
+  assert_eq!(
+    meteo
+      .hourly
+      .time
+      .len(),
+    3
+  );
+  assert_eq!(
+    meteo
+      .hourly
+      .weather_code[2],
+    Some(1.0)
+  );
+  assert_eq!(
+    meteo
+      .daily
+      .time,
+    vec!["2024-09-21", "2024-09-22"]
+  );
+  assert_eq!(
+    meteo
+      .daily
+      .temperature_2_m_max[0],
+    Some(15.2)
+  );
+  assert_eq!(
+    meteo
+      .daily
+      .temperature_2_m_min[0],
+    Some(14.8)
+  );
+  assert!(
+    (meteo
+      .daily
+      .precipitation_sum[0]
+      .unwrap()
+      - 1.2)
+      .abs()
+      < 0.001
+  );
+}
+
+#[tokio::test]
+async fn met_norway_estimates_an_offset_without_a_timezone() {
+  let server = StandIn::serve(vec![(
+    "/weatherapi/locationforecast/2.0/compact",
+    fixture("api.met.no/weatherapi/locationforecast/2.0/compact.json"),
+  )])
+  .await;
+  let provider = MetNorway::new(
+    format!("{}/weatherapi/locationforecast/2.0/compact", server.url),
+    live(),
+  );
+
+  let meteo = provider
+    .fetch(&ForecastRequest {
+      lat: 59.9139,
+      lng: 10.7522,
+      ..request(UnitSystem::Metric)
+    })
+    .await
+    .unwrap();
+
+  assert_eq!(meteo.timezone, "+01:00");
+  assert_eq!(meteo.timezone_abbreviation, "UTC+01:00 (approximate)");
+  assert_eq!(meteo.utc_offset_seconds, 3600.0);
+  assert_eq!(
+    meteo
+      .current
+      .time,
+    "2024-09-21T14:00"
+  );
+}
+
+#[tokio::test]
+async fn nws_resolves_gridpoint_then_maps_hourly_forecast() {
+  let server = StandIn::serve(vec![
+    (
+      "/points/",
+      fixture("api.weather.gov/points/40.7128,-74.0060.json"),
+    ),
+    (
+      "/gridpoints/OKX/33,35/forecast/hourly",
+      fixture("api.weather.gov/gridpoints/OKX/33,35/forecast/hourly.json"),
+    ),
+  ])
+  .await;
+  let provider = Nws::new(
+    server
+      .url
+      .clone(),
+    live(),
+  );
+
+  let meteo = provider
+    .fetch(&request(UnitSystem::Imperial))
+    .await
+    .unwrap();
+
+  assert_eq!(meteo.timezone, "America/New_York");
+  assert_eq!(meteo.utc_offset_seconds, -14400.0);
+  assert_eq!(
+    meteo
+      .current
+      .time,
+    "2024-09-21T14:00"
+  );
+  assert_eq!(
+    meteo
+      .current
+      .temperature_2_m,
+    Some(72.0)
+  );
+  assert_eq!(
+    meteo
+      .current
+      .weather_code,
+    Some(2)
+  );
+  assert_eq!(
+    meteo
+      .current
+      .wind_direction_10_m,
+    Some(112.5)
+  );
+  assert_eq!(
+    meteo
+      .hourly
+      .weather_code[1],
+    Some(80.0)
+  );
+  assert_eq!(
+    meteo
+      .hourly
+      .wind_gusts_10_m,
+    Vec::<Option<f64>>::new()
+  );
+  // dew point arrives in °C and is converted to the requested °F
+  assert_eq!(
+    meteo
+      .hourly_units
+      .dew_point_2_m,
+    "°F"
+  );
+  assert!(
+    (meteo
+      .hourly
+      .dew_point_2_m[0]
+      .unwrap()
+      - 55.94)
+      .abs()
+      < 0.01
+  );
+  assert_eq!(
+    meteo
+      .daily
+      .temperature_2_m_max[0],
+    Some(73.0)
+  );
+
+  assert_eq!(
+    server.requests(),
+    vec![
+      String::from("/points/40.7128,-74.0060"),
+      String::from("/gridpoints/OKX/33,35/forecast/hourly"),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn fetch_failures_are_typed() {
+  let server = StandIn::serve(vec![(
+    "/points",
+    fixture("api.weather.gov/points/40.7128,-74.0060.json"),
+  )])
+  .await;
+
+  let missing = OpenMeteo::new(format!("{}/v1/forecast", server.url), live())
+    .fetch(&request(UnitSystem::Imperial))
+    .await;
+  assert_eq!(missing, Err(FetchError::Status(404)));
+
+  // a points response is valid JSON, just not a forecast
+  let wrong_shape = OpenMeteo::new(format!("{}/points", server.url), live())
+    .fetch(&request(UnitSystem::Imperial))
+    .await;
+  assert!(matches!(wrong_shape, Err(FetchError::Parse(_))));
+
+  let unreachable = OpenMeteo::new("http://127.0.0.1:1/v1/forecast", live())
+    .fetch(&request(UnitSystem::Imperial))
+    .await;
+  assert!(matches!(unreachable, Err(FetchError::Network(_))));
+}
+
+#[tokio::test]
+async fn open_meteo_fetches_many_places_in_one_request() {
+  let server = StandIn::serve(vec![(
+    "/v1/forecast",
+    fixture("api.open-meteo.com/v1/forecast-batch.json"),
+  )])
+  .await;
+  let provider = OpenMeteo::new(format!("{}/v1/forecast", server.url), live());
+  let london = ForecastRequest {
+    lat: 51.5,
+    lng: -0.12,
+    timezone: String::from("Europe/London"),
+    ..request(UnitSystem::Imperial)
+  };
+
+  let forecasts = provider
+    .fetch_many(&[request(UnitSystem::Imperial), london])
+    .await
+    .unwrap();
+
+  assert_eq!(
+    forecasts
+      .iter()
+      .map(|meteo| meteo
+        .timezone
+        .as_str())
+      .collect::<Vec<_>>(),
+    ["America/New_York", "Europe/London"]
+  );
+  let requests = server.requests();
+  assert_eq!(requests.len(), 1);
+  assert!(requests[0].contains("latitude=40.7128%2C51.5"));
+  assert!(requests[0].contains("longitude=-74.006%2C-0.12"));
+  assert!(requests[0].contains("timezone=auto%2CEurope%2FLondon"));
+}
+
+#[tokio::test]
+async fn open_meteo_batches_must_answer_every_place() {
+  // one forecast where two were asked for
+  let server = StandIn::serve(vec![(
+    "/v1/forecast",
+    fixture("api.open-meteo.com/v1/forecast.json"),
+  )])
+  .await;
+  let provider = OpenMeteo::new(format!("{}/v1/forecast", server.url), live());
+
+  let result = provider
+    .fetch_many(&[request(UnitSystem::Imperial), request(UnitSystem::Imperial)])
+    .await;
+
+  assert!(matches!(result, Err(FetchError::Parse(_))));
+}
+
+#[tokio::test]
+async fn recordings_of_single_and_batched_requests_are_kept_apart() {
+  let dir = std::env::temp_dir().join(format!("rain-recordings-{}", std::process::id()));
+  // the same host and path, answering one place or two
+  let single = StandIn::serve(vec![(
+    "/v1/forecast",
+    fixture("api.open-meteo.com/v1/forecast.json"),
+  )])
+  .await;
+  let batched = StandIn::serve(vec![(
+    "/v1/forecast",
+    fixture("api.open-meteo.com/v1/forecast-batch.json"),
+  )])
+  .await;
+  let london = ForecastRequest {
+    lat: 51.5,
+    lng: -0.12,
+    timezone: String::from("Europe/London"),
+    ..request(UnitSystem::Imperial)
+  };
+  let record = HttpClient::new(HttpMode::Record(dir.clone()));
+  OpenMeteo::new(format!("{}/v1/forecast", single.url), record.clone())
+    .fetch(&request(UnitSystem::Imperial))
+    .await
+    .unwrap();
+  OpenMeteo::new(format!("{}/v1/forecast", batched.url), record)
+    .fetch_many(&[request(UnitSystem::Imperial), london.clone()])
+    .await
+    .unwrap();
+
+  let replay = OpenMeteo::new(
+    format!("{}/v1/forecast", single.url),
+    HttpClient::new(HttpMode::Replay(dir.clone())),
+  );
+  let one = replay
+    .fetch(&request(UnitSystem::Imperial))
+    .await;
+  let both = replay
+    .fetch_many(&[request(UnitSystem::Imperial), london])
+    .await;
+  std::fs::remove_dir_all(&dir).unwrap();
+
+  assert_eq!(
+    one
+      .unwrap()
+      .timezone,
+    "America/New_York"
+  );
+  assert_eq!(
+    both
+      .unwrap()
+      .len(),
+    2
+  );
+}
+
+#[tokio::test]
+async fn recording_fails_the_fetch_when_the_response_cant_be_saved() {
+  let server = StandIn::serve(vec![(
+    "/v1/forecast",
+    fixture("api.open-meteo.com/v1/forecast.json"),
+  )])
+  .await;
+  // a file where the recordings directory should be
+  let record = HttpClient::new(HttpMode::Record(fixture(
+    "api.open-meteo.com/v1/forecast.json",
+  )));
+
+  let result = OpenMeteo::new(format!("{}/v1/forecast", server.url), record)
+    .fetch(&request(UnitSystem::Imperial))
+    .await;
+
+  assert!(matches!(result, Err(FetchError::Network(_))));
+}
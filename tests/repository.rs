@@ -0,0 +1,517 @@
+mod common;
+
+use chrono::{NaiveDate, TimeDelta, TimeZone, Utc};
+use common::forecast_fixture;
+use rain::dashboard::DashboardCell;
+use rain::db_models::{Location, RetentionPolicy};
+use rain::providers::ProviderKind;
+use rain::queries::Repository;
+use rain::units::{UnitPreferences, UnitSystem};
+use sqlx::sqlite::SqlitePoolOptions;
+
+#[tokio::test]
+async fn in_memory_repositories_are_isolated() {
+  let first = Repository::in_memory()
+    .await
+    .unwrap();
+  let second = Repository::in_memory()
+    .await
+    .unwrap();
+
+  first
+    .save_location("Home", "dr5regw3p", ProviderKind::OpenMeteo)
+    .await
+    .unwrap();
+
+  assert_eq!(
+    first
+      .get_all_locations()
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+  assert!(second
+    .get_all_locations()
+    .await
+    .unwrap()
+    .is_empty());
+}
+
+#[tokio::test]
+async fn clones_share_the_pool() {
+  let repo = Repository::in_memory()
+    .await
+    .unwrap();
+  let prefs = UnitPreferences::preset(UnitSystem::Metric);
+
+  repo
+    .clone()
+    .save_unit_preferences(&prefs)
+    .await
+    .unwrap();
+
+  assert_eq!(
+    repo
+      .get_unit_preferences()
+      .await
+      .unwrap(),
+    prefs
+  );
+}
+
+const GEOHASHES: [&str; 3] = ["dr5regw3p", "dr5ru7", "u4xsudvx"];
+
+async fn with_locations(names: &[&str]) -> (Repository, Vec<i64>) {
+  let repo = Repository::in_memory()
+    .await
+    .unwrap();
+  for (name, geohash) in names
+    .iter()
+    .zip(GEOHASHES)
+  {
+    repo
+      .save_location(name, geohash, ProviderKind::OpenMeteo)
+      .await
+      .unwrap();
+  }
+  let ids = repo
+    .get_all_locations()
+    .await
+    .unwrap()
+    .iter()
+    .map(|location| location.id)
+    .collect();
+  (repo, ids)
+}
+
+fn names(locations: &[Location]) -> Vec<&str> {
+  locations
+    .iter()
+    .map(|location| {
+      location
+        .name
+        .as_str()
+    })
+    .collect()
+}
+
+#[tokio::test]
+async fn locations_keep_the_order_they_were_arranged_in() {
+  let (repo, ids) = with_locations(&["Home", "Work", "Cabin"]).await;
+
+  repo
+    .reorder_locations(&[ids[2], ids[0], ids[1]])
+    .await
+    .unwrap();
+
+  let locations = repo
+    .get_all_locations()
+    .await
+    .unwrap();
+  assert_eq!(names(&locations), ["Cabin", "Home", "Work"]);
+}
+
+#[tokio::test]
+async fn current_location_falls_back_to_the_first_in_the_list() {
+  let (repo, ids) = with_locations(&["Home", "Work"]).await;
+  assert_eq!(
+    repo
+      .get_current_location()
+      .await
+      .unwrap()
+      .name,
+    "Home"
+  );
+
+  repo
+    .set_current_location(ids[1])
+    .await
+    .unwrap();
+  assert_eq!(
+    repo
+      .get_current_location()
+      .await
+      .unwrap()
+      .name,
+    "Work"
+  );
+
+  repo
+    .delete_location(ids[1])
+    .await
+    .unwrap();
+  assert_eq!(
+    repo
+      .get_current_location()
+      .await
+      .unwrap()
+      .name,
+    "Home"
+  );
+}
+
+#[tokio::test]
+async fn deleting_a_location_deletes_its_history() {
+  let (repo, ids) = with_locations(&["Home"]).await;
+  let location = repo
+    .get_latest_location()
+    .await
+    .unwrap();
+  repo
+    .add_forecast_to_db(&location, &forecast_fixture(), Utc::now())
+    .await
+    .unwrap();
+
+  repo
+    .rename_location(ids[0], "Old home")
+    .await
+    .unwrap();
+  assert_eq!(
+    repo
+      .get_latest_location()
+      .await
+      .unwrap()
+      .name,
+    "Old home"
+  );
+
+  repo
+    .delete_location(ids[0])
+    .await
+    .unwrap();
+  assert!(repo
+    .get_latest_historical_forecast(ids[0])
+    .await
+    .is_err());
+}
+
+#[tokio::test]
+async fn saving_the_same_place_twice_updates_it() {
+  let repo = Repository::in_memory()
+    .await
+    .unwrap();
+
+  let first = repo
+    .save_location("Home", "dr5regw3p", ProviderKind::OpenMeteo)
+    .await
+    .unwrap();
+  let second = repo
+    .save_location("Apartment", "dr5regw3p", ProviderKind::Nws)
+    .await
+    .unwrap();
+
+  assert_eq!(second.id, first.id);
+  assert_eq!(second.name, "Apartment");
+  assert_eq!(second.provider(), ProviderKind::Nws);
+  assert_eq!(
+    repo
+      .get_all_locations()
+      .await
+      .unwrap(),
+    vec![second]
+  );
+}
+
+#[tokio::test]
+async fn geohashes_are_made_unique_around_orphaned_history() {
+  // INFO: stop just short of the migration, so there's a database from before it to fill
+  const UNIQUE_GEOHASH: i64 = 20240928164233;
+  let pool = SqlitePoolOptions::new()
+    .max_connections(1)
+    .min_connections(1)
+    .idle_timeout(None)
+    .max_lifetime(None)
+    .connect("sqlite::memory:")
+    .await
+    .unwrap();
+  let mut before = sqlx::migrate!();
+  before.migrations = before
+    .migrations
+    .iter()
+    .filter(|migration| migration.version < UNIQUE_GEOHASH)
+    .cloned()
+    .collect();
+  before
+    .run(&pool)
+    .await
+    .unwrap();
+
+  // a duplicate of Home holding its history and the current location, and a forecast whose
+  // location was deleted while foreign keys weren't enforced
+  sqlx::raw_sql(
+    "
+      pragma foreign_keys = off;
+      insert into Location (id, geohash, name) values
+        (1, 'dr5regw3p', 'Home'),
+        (2, 'dr5regw3p', 'Home again'),
+        (3, 'u4xsudvx', 'Oslo');
+      insert into HistoricalForecast (location_id, response, timestamp) values
+        (2, '{}', '2024-09-21 15:30:12 UTC'),
+        (9, '{}', '2024-09-21 15:30:12 UTC');
+      insert into Setting (key, value) values ('current_location', '2');
+      pragma foreign_keys = on;
+    ",
+  )
+  .execute(&pool)
+  .await
+  .unwrap();
+
+  sqlx::migrate!()
+    .run(&pool)
+    .await
+    .unwrap();
+
+  let locations: Vec<i64> = sqlx::query_scalar("select id from Location order by id")
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+  assert_eq!(locations, vec![1, 3]);
+  let history: Vec<i64> = sqlx::query_scalar("select location_id from HistoricalForecast")
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+  assert_eq!(history, vec![1]);
+  let current: String =
+    sqlx::query_scalar("select value from Setting where key = 'current_location'")
+      .fetch_one(&pool)
+      .await
+      .unwrap();
+  assert_eq!(current, "1");
+}
+
+#[tokio::test]
+async fn latest_forecast_is_the_newest_fetch() {
+  let (repo, _) = with_locations(&["Home"]).await;
+  let location = repo
+    .get_latest_location()
+    .await
+    .unwrap();
+  let mut newer = forecast_fixture();
+  newer.timezone = String::from("newer");
+  let mut older = forecast_fixture();
+  older.timezone = String::from("older");
+  let now = Utc::now();
+
+  // inserted out of order, so row ids don't give the answer away
+  repo
+    .add_forecast_to_db(&location, &newer, now)
+    .await
+    .unwrap();
+  repo
+    .add_forecast_to_db(&location, &older, now - TimeDelta::hours(1))
+    .await
+    .unwrap();
+
+  let latest = repo
+    .get_latest_historical_forecast(location.id)
+    .await
+    .unwrap();
+  assert_eq!(
+    latest
+      .meteo()
+      .unwrap()
+      .timezone,
+    "newer"
+  );
+  assert_eq!(
+    latest
+      .timestamp
+      .timestamp_millis(),
+    now.timestamp_millis()
+  );
+}
+
+#[tokio::test]
+async fn history_reads_values_from_the_newest_fetch() {
+  let (repo, _) = with_locations(&["Home"]).await;
+  let location = repo
+    .get_latest_location()
+    .await
+    .unwrap();
+  let older = forecast_fixture();
+  let mut newer = forecast_fixture();
+  newer
+    .hourly
+    .precipitation[1] = Some(0.5);
+  newer
+    .daily
+    .precipitation_sum[0] = Some(0.75);
+  let now = Utc::now();
+  repo
+    .add_forecast_to_db(&location, &older, now - TimeDelta::hours(1))
+    .await
+    .unwrap();
+  repo
+    .add_forecast_to_db(&location, &newer, now)
+    .await
+    .unwrap();
+
+  // 13:00 to 16:00 in New York
+  let from = Utc
+    .with_ymd_and_hms(2024, 9, 21, 17, 0, 0)
+    .unwrap();
+  let hourly = repo
+    .get_hourly_history(
+      location.id,
+      "precipitation",
+      from,
+      from + TimeDelta::hours(3),
+    )
+    .await
+    .unwrap();
+  assert_eq!(
+    hourly
+      .iter()
+      .map(|hour| hour.value)
+      .collect::<Vec<_>>(),
+    [Some(0.0), Some(0.5), Some(0.0)]
+  );
+  assert_eq!(hourly[0].valid_time, from);
+  assert_eq!(hourly[1].unit, "inch");
+
+  let day = NaiveDate::from_ymd_opt(2024, 9, 21).unwrap();
+  let daily = repo
+    .get_daily_history(location.id, "precipitation_sum", day, day)
+    .await
+    .unwrap();
+  assert_eq!(daily.len(), 1);
+  assert_eq!(daily[0].value, Some(0.75));
+  assert_eq!(daily[0].valid_date, day);
+}
+
+#[tokio::test]
+async fn pruning_thins_old_history_to_one_fetch_a_day() {
+  let (repo, _) = with_locations(&["Home"]).await;
+  let location = repo
+    .get_latest_location()
+    .await
+    .unwrap();
+  let now = Utc
+    .with_ymd_and_hms(2024, 10, 1, 12, 0, 0)
+    .unwrap();
+  let fetches = [
+    (now - TimeDelta::hours(1), "recent"),
+    (now - TimeDelta::hours(2), "also recent"),
+    (now - TimeDelta::days(10) - TimeDelta::hours(4), "morning"),
+    (now - TimeDelta::days(10), "noon"),
+    (now - TimeDelta::days(40), "ancient"),
+  ];
+  for (fetched_at, label) in fetches {
+    let mut meteo = forecast_fixture();
+    meteo.timezone = String::from(label);
+    repo
+      .add_forecast_to_db(&location, &meteo, fetched_at)
+      .await
+      .unwrap();
+  }
+
+  let policy = RetentionPolicy {
+    keep_all_days: 7,
+    keep_daily_days: Some(30),
+  };
+  assert_eq!(
+    repo
+      .prune(&policy, now)
+      .await
+      .unwrap(),
+    2
+  );
+  // running it again finds nothing left to do
+  assert_eq!(
+    repo
+      .prune(&policy, now)
+      .await
+      .unwrap(),
+    0
+  );
+
+  assert_eq!(
+    repo
+      .get_latest_historical_forecast(location.id)
+      .await
+      .unwrap()
+      .meteo()
+      .unwrap()
+      .timezone,
+    "recent"
+  );
+
+  repo
+    .compact()
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn pruning_goes_by_the_locations_own_days() {
+  let (repo, _) = with_locations(&["Home"]).await;
+  let location = repo
+    .get_latest_location()
+    .await
+    .unwrap();
+  let now = Utc
+    .with_ymd_and_hms(2024, 10, 1, 12, 0, 0)
+    .unwrap();
+  // the same day in UTC, but the evening before and the next morning in New York
+  for fetched_at in [
+    Utc
+      .with_ymd_and_hms(2024, 9, 21, 2, 0, 0)
+      .unwrap(),
+    Utc
+      .with_ymd_and_hms(2024, 9, 21, 12, 0, 0)
+      .unwrap(),
+  ] {
+    repo
+      .add_forecast_to_db(&location, &forecast_fixture(), fetched_at)
+      .await
+      .unwrap();
+  }
+
+  let policy = RetentionPolicy {
+    keep_all_days: 7,
+    keep_daily_days: Some(30),
+  };
+  assert_eq!(
+    repo
+      .prune(&policy, now)
+      .await
+      .unwrap(),
+    0
+  );
+}
+
+#[tokio::test]
+async fn dashboard_cells_default_until_chosen() {
+  let repo = Repository::in_memory()
+    .await
+    .unwrap();
+  assert_eq!(
+    repo
+      .get_dashboard_cells()
+      .await
+      .unwrap(),
+    DashboardCell::DEFAULT
+  );
+
+  let cells = [DashboardCell::WindGusts, DashboardCell::Temperature];
+  repo
+    .save_dashboard_cells(&cells)
+    .await
+    .unwrap();
+  assert_eq!(
+    repo
+      .get_dashboard_cells()
+      .await
+      .unwrap(),
+    cells
+  );
+
+  // each cell reads its own variable
+  let current = forecast_fixture().current;
+  assert_eq!(DashboardCell::Temperature.value(&current), Some(71.6));
+  assert_eq!(
+    DashboardCell::WindGusts.value(&current),
+    current.wind_gusts_10_m
+  );
+}
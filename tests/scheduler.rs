@@ -0,0 +1,111 @@
+use chrono::{DateTime, TimeDelta, TimeZone, Utc};
+use rain::scheduler::{RefreshSchedule, Scheduler};
+
+fn start() -> DateTime<Utc> {
+  Utc
+    .with_ymd_and_hms(2024, 10, 1, 12, 0, 0)
+    .unwrap()
+}
+
+/// Ticks once a minute from `from` up to and including `to`, returning when refreshes were due.
+fn run(
+  scheduler: &mut Scheduler,
+  schedule: &RefreshSchedule,
+  from: DateTime<Utc>,
+  to: DateTime<Utc>,
+) -> Vec<i64> {
+  let mut due = vec![];
+  let mut now = from;
+  while now <= to {
+    if scheduler.tick(schedule, now) {
+      scheduler.attempted(now);
+      due.push((now - start()).num_minutes());
+    }
+    now += TimeDelta::minutes(1);
+  }
+  due
+}
+
+#[test]
+fn refreshes_on_the_interval() {
+  let mut scheduler = Scheduler::default();
+  let schedule = RefreshSchedule {
+    interval_minutes: Some(30),
+    all_locations: false,
+  };
+
+  let due = run(
+    &mut scheduler,
+    &schedule,
+    start(),
+    start() + TimeDelta::minutes(90),
+  );
+
+  assert_eq!(due, [0, 30, 60, 90]);
+}
+
+#[test]
+fn never_refreshes_when_turned_off() {
+  let mut scheduler = Scheduler::default();
+  let schedule = RefreshSchedule {
+    interval_minutes: None,
+    all_locations: false,
+  };
+
+  assert!(run(
+    &mut scheduler,
+    &schedule,
+    start(),
+    start() + TimeDelta::hours(3)
+  )
+  .is_empty());
+}
+
+#[test]
+fn failures_back_off_until_a_refresh_succeeds() {
+  let mut scheduler = Scheduler::default();
+  let schedule = RefreshSchedule::default();
+  assert!(scheduler.tick(&schedule, start()));
+
+  scheduler.failed();
+  assert_eq!(scheduler.wait(&schedule), Some(TimeDelta::minutes(60)));
+  scheduler.failed();
+  assert_eq!(scheduler.wait(&schedule), Some(TimeDelta::minutes(120)));
+  for _ in 0..10 {
+    scheduler.failed();
+  }
+  assert_eq!(scheduler.wait(&schedule), Some(Scheduler::MAX_BACKOFF));
+
+  scheduler.succeeded();
+  assert_eq!(scheduler.wait(&schedule), Some(TimeDelta::minutes(30)));
+}
+
+#[test]
+fn catches_up_after_waking_from_suspend() {
+  let mut scheduler = Scheduler::default();
+  let schedule = RefreshSchedule {
+    interval_minutes: Some(180),
+    all_locations: false,
+  };
+  assert!(scheduler.tick(&schedule, start()));
+  scheduler.attempted(start());
+  assert!(!scheduler.tick(&schedule, start() + TimeDelta::minutes(1)));
+
+  // asleep for twenty minutes, well short of the interval
+  assert!(scheduler.tick(&schedule, start() + TimeDelta::minutes(21)));
+  scheduler.attempted(start() + TimeDelta::minutes(21));
+  assert!(!scheduler.tick(&schedule, start() + TimeDelta::minutes(22)));
+}
+
+#[test]
+fn a_due_tick_that_sent_nothing_stays_due() {
+  let mut scheduler = Scheduler::default();
+  let schedule = RefreshSchedule::default();
+
+  // nothing to refresh yet
+  assert!(scheduler.tick(&schedule, start()));
+  assert!(scheduler.tick(&schedule, start() + TimeDelta::minutes(1)));
+
+  scheduler.attempted(start() + TimeDelta::minutes(1));
+  assert!(!scheduler.tick(&schedule, start() + TimeDelta::minutes(2)));
+}
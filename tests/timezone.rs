@@ -0,0 +1,90 @@
+mod common;
+
+use chrono::{FixedOffset, NaiveDate, TimeDelta};
+use common::{fixture_now, forecast_fixture};
+use rain::timezone::LocalZone;
+
+#[test]
+fn times_past_a_dst_change_get_that_days_offset() {
+  // fetched in September, while New York was on EDT
+  let meteo = forecast_fixture();
+  assert_eq!(meteo.utc_offset_seconds, -14400.0);
+
+  let before = meteo
+    .parse_time("2024-11-02T12:00")
+    .unwrap();
+  let after = meteo
+    .parse_time("2024-11-04T12:00")
+    .unwrap();
+
+  assert_eq!(
+    before
+      .offset()
+      .local_minus_utc(),
+    -4 * 3600
+  );
+  assert_eq!(
+    after
+      .offset()
+      .local_minus_utc(),
+    -5 * 3600
+  );
+}
+
+#[test]
+fn a_repeated_hour_is_the_first_one() {
+  let zone = LocalZone::parse("America/New_York", FixedOffset::east_opt(0).unwrap());
+  let time = NaiveDate::from_ymd_opt(2024, 11, 3)
+    .unwrap()
+    .and_hms_opt(1, 30, 0)
+    .unwrap();
+
+  assert_eq!(
+    zone
+      .from_local(&time)
+      .unwrap()
+      .offset()
+      .local_minus_utc(),
+    -4 * 3600
+  );
+}
+
+#[test]
+fn names_that_arent_zones_fall_back_to_the_offset() {
+  let mut meteo = forecast_fixture();
+  meteo.timezone = String::from("+01:00");
+  meteo.utc_offset_seconds = 3600.0;
+
+  assert_eq!(
+    meteo.zone(),
+    LocalZone::Fixed(FixedOffset::east_opt(3600).unwrap())
+  );
+  assert_eq!(
+    meteo
+      .parse_time("2024-11-04T12:00")
+      .unwrap()
+      .offset()
+      .local_minus_utc(),
+    3600
+  );
+}
+
+#[test]
+fn now_indexes_the_first_hour_and_day_not_yet_over() {
+  let meteo = forecast_fixture();
+  let hours = &meteo
+    .hourly
+    .time;
+  let days = &meteo
+    .daily
+    .time;
+
+  // 14:15 is still in the 14:00 hour
+  assert_eq!(meteo.index_of_now(hours, fixture_now()), 1);
+  assert_eq!(meteo.index_of_now(days, fixture_now()), 0);
+
+  // by the next morning every hour in the fixture is over
+  let tomorrow = fixture_now() + TimeDelta::hours(13);
+  assert_eq!(meteo.index_of_now(hours, tomorrow), 3);
+  assert_eq!(meteo.index_of_now(days, tomorrow), 1);
+}
@@ -0,0 +1,11 @@
+use chrono::TimeDelta;
+use rain::views::format_age;
+
+#[test]
+fn format_age_picks_the_largest_whole_unit() {
+  assert_eq!(format_age(TimeDelta::seconds(30)), "just now");
+  assert_eq!(format_age(TimeDelta::minutes(1)), "1 minute ago");
+  assert_eq!(format_age(TimeDelta::minutes(42)), "42 minutes ago");
+  assert_eq!(format_age(TimeDelta::minutes(150)), "2 hours ago");
+  assert_eq!(format_age(TimeDelta::days(3)), "3 days ago");
+}
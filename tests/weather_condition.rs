@@ -0,0 +1,83 @@
+mod common;
+
+use common::forecast_fixture;
+use rain::weather_condition::{Severity, WeatherCondition};
+
+#[test]
+fn codes_round_trip() {
+  for condition in WeatherCondition::ALL {
+    assert_eq!(
+      WeatherCondition::from_code(condition.code()),
+      Some(condition)
+    );
+  }
+}
+
+#[test]
+fn open_meteo_codes_mean_what_open_meteo_says() {
+  assert_eq!(
+    WeatherCondition::from_code(2).map(|condition| condition.short()),
+    Some("Partly cloudy")
+  );
+  assert_eq!(WeatherCondition::from_code(45), Some(WeatherCondition::Fog));
+  assert_eq!(
+    WeatherCondition::from_code(65),
+    Some(WeatherCondition::HeavyRain)
+  );
+  // in the full WMO table, but not something Open-Meteo sends
+  assert_eq!(WeatherCondition::from_code(4), None);
+  assert_eq!(WeatherCondition::from_value(2.5), None);
+  assert_eq!(
+    WeatherCondition::from_value(80.0),
+    Some(WeatherCondition::SlightRainShowers)
+  );
+}
+
+#[test]
+fn severity_rises_with_intensity() {
+  assert_eq!(WeatherCondition::ClearSky.severity(), Severity::Calm);
+  assert!(WeatherCondition::SlightRain.severity() < WeatherCondition::HeavyRain.severity());
+  assert_eq!(
+    WeatherCondition::ThunderstormWithHeavyHail.severity(),
+    Severity::Severe
+  );
+}
+
+#[test]
+fn clear_and_showers_have_night_icons() {
+  assert_ne!(
+    WeatherCondition::ClearSky.icon(true),
+    WeatherCondition::ClearSky.icon(false)
+  );
+  assert_ne!(
+    WeatherCondition::SlightRainShowers.icon(true),
+    WeatherCondition::SlightRainShowers.icon(false)
+  );
+  // nothing to see of the sky through fog, day or night
+  assert_eq!(
+    WeatherCondition::Fog.icon(true),
+    WeatherCondition::Fog.icon(false)
+  );
+  for condition in WeatherCondition::ALL {
+    assert!(condition
+      .icon(true)
+      .starts_with("<svg"));
+  }
+}
+
+#[test]
+fn night_falls_between_sunset_and_sunrise() {
+  let mut meteo = forecast_fixture();
+  assert!(meteo.is_day_at("2024-09-21T14:00"));
+  assert!(!meteo.is_day_at("2024-09-21T19:00"));
+  assert!(!meteo.is_day_at("2024-09-22T05:00"));
+  assert!(meteo.is_day_now());
+
+  // polar night
+  meteo
+    .daily
+    .daylight_duration[0] = Some(0.0);
+  assert!(!meteo.is_day_at("2024-09-21T14:00"));
+  // and a day the forecast doesn't cover
+  assert!(meteo.is_day_at("2024-10-01T02:00"));
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::forecast_fixture;
+use rain::units::cardinal;
+use rain::wind_compass::wind_rose;
+
+#[test]
+fn bearings_name_the_nearest_point() {
+  assert_eq!(cardinal(0.0), "N");
+  assert_eq!(cardinal(112.0), "ESE");
+  assert_eq!(cardinal(350.0), "N");
+  assert_eq!(cardinal(348.0), "NNW");
+  assert_eq!(cardinal(-90.0), "W");
+  assert_eq!(cardinal(720.0 + 22.5), "NNE");
+}
+
+#[test]
+fn the_rose_counts_hours_per_sector() {
+  let rose = wind_rose(&[Some(0.0), Some(359.0), None, Some(90.0), Some(100.0)]);
+
+  // 359° is as good as north, and 100° is nearer east than east-southeast
+  assert_eq!(rose[0], 2);
+  assert_eq!(rose[4], 2);
+  assert_eq!(rose[5], 0);
+  assert_eq!(
+    rose
+      .iter()
+      .sum::<usize>(),
+    4
+  );
+}
+
+#[test]
+fn hourly_wind_is_parsed() {
+  let meteo = forecast_fixture();
+
+  assert_eq!(
+    meteo
+      .hourly
+      .wind_direction_10_m,
+    [Some(108.0), Some(112.0), Some(117.0)]
+  );
+  assert_eq!(
+    meteo
+      .hourly_units
+      .wind_speed_10_m,
+    "mph"
+  );
+}